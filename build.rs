@@ -71,6 +71,11 @@ fn make_data() -> Context {
         "SIOCSIFFLAGS",
         "SIOCGIFMTU",
         "SIOCSIFMTU",
+        "SIOCGIFINDEX",
+        "TUNSETIFF",     // Only on Linux
+        "TUNSETPERSIST", // Only on Linux
+        "TUNSETOWNER",   // Only on Linux
+        "TUNSETGROUP",   // Only on Linux
         // Address families
         "AF_LINK",
         "AF_PACKET", // Only on Linux
@@ -80,11 +85,11 @@ fn make_data() -> Context {
     let anames: &[&str] = &["sizeof(struct ifreq)"];
 
     let names = names
-        .into_iter()
+        .iter()
         .map(|x| String::from(*x))
         .collect::<Vec<String>>();
     let anames = anames
-        .into_iter()
+        .iter()
         .map(|x| String::from(*x))
         .collect::<Vec<String>>();
 
@@ -100,27 +105,29 @@ struct Context {
     always_constants: Vec<String>,
 }
 
+// The wrapped errors are only ever shown through `Debug`.
+#[allow(dead_code)]
 #[derive(Debug)]
 enum Error {
-    IoError(io::Error),
-    TemplateError(hbs::TemplateError),
-    RenderError(hbs::RenderError),
+    Io(io::Error),
+    Template(Box<hbs::TemplateError>),
+    Render(hbs::RenderError),
 }
 
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Error {
-        Error::IoError(e)
+        Error::Io(e)
     }
 }
 
 impl From<hbs::TemplateError> for Error {
     fn from(e: hbs::TemplateError) -> Error {
-        Error::TemplateError(e)
+        Error::Template(Box::new(e))
     }
 }
 
 impl From<hbs::RenderError> for Error {
     fn from(e: hbs::RenderError) -> Error {
-        Error::RenderError(e)
+        Error::Render(e)
     }
 }
@@ -1,15 +1,12 @@
 extern crate interfaces;
 
-use std::iter;
-use std::net;
-
 use interfaces::{
     flags::{self, InterfaceFlags},
     Interface, Kind,
 };
 
 // Flag mappings that ifconfig uses, in order.
-const NAME_MAPPINGS: &'static [(flags::InterfaceFlags, &'static str)] = &[
+const NAME_MAPPINGS: &[(flags::InterfaceFlags, &str)] = &[
     (InterfaceFlags::IFF_UP, "UP"),
     (InterfaceFlags::IFF_LOOPBACK, "LOOPBACK"),
     (InterfaceFlags::IFF_BROADCAST, "BROADCAST"),
@@ -26,17 +23,15 @@ fn main() {
     // Find the maximum alignment for our interface names.
     let max_align = ifs.iter().map(|i| i.name.len() + 2).max().unwrap();
 
-    let full_align = iter::repeat(' ').take(max_align).collect::<String>();
+    let full_align = " ".repeat(max_align);
 
     for i in ifs.iter() {
-        let name_align = iter::repeat(' ')
-            .take(max_align - i.name.len() - 2)
-            .collect::<String>();
+        let name_align = " ".repeat(max_align - i.name.len() - 2);
 
         // Build the first line by printing the interface flags.
         let first_line = {
             let mut buf = String::new();
-            buf.push_str(&*format!("flags={} <", i.flags.bits()));
+            buf.push_str(&format!("flags={} <", i.flags.bits()));
 
             let mut flag_strs = vec![];
             for &(f, s) in NAME_MAPPINGS.iter() {
@@ -45,8 +40,8 @@ fn main() {
                 }
             }
 
-            buf.push_str(&*flag_strs.join(","));
-            buf.push_str(&*format!("> mtu {}", i.get_mtu().unwrap_or(0)));
+            buf.push_str(&flag_strs.join(","));
+            buf.push_str(&format!("> mtu {}", i.get_mtu().unwrap_or(0)));
 
             buf
         };
@@ -62,8 +57,8 @@ fn main() {
         }
 
         for addr in i.addresses.iter() {
-            let raddr = match addr.addr {
-                Some(a) => a,
+            let ip = match addr.ip {
+                Some(ip) => ip,
                 None => continue,
             };
 
@@ -73,14 +68,10 @@ fn main() {
                 _ => continue,
             };
 
-            println!("{}{} {}", full_align, prefix, format_addr(&raddr));
+            match addr.network() {
+                Ok(Some(net)) => println!("{}{} {}", full_align, prefix, net),
+                _ => println!("{}{} {}", full_align, prefix, ip),
+            }
         }
     }
 }
-
-fn format_addr(addr: &net::SocketAddr) -> String {
-    match addr {
-        &net::SocketAddr::V4(ref a) => format!("{}", a.ip()),
-        &net::SocketAddr::V6(ref a) => format!("{}", a.ip()),
-    }
-}
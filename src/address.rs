@@ -0,0 +1,713 @@
+//! Metadata of interface addresses that `getifaddrs` does not report, i.e. the flags, scope,
+//! lifetimes and label of each address.  These are read over rtnetlink (`RTM_GETADDR`) and filled
+//! into the `Address` structures of an `Interface`.  This is only available on Linux.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::net;
+use std::str;
+use std::time::Duration;
+
+use libc;
+
+use netlink::{self, Message, Request, Socket};
+use route::RouteScope;
+use {Address, HardwareAddr, HardwareAddrError, Interface, Kind, Result};
+
+/// The length of `struct ifaddrmsg`.
+const IFADDRMSG_LEN: usize = 8;
+
+// Attributes of address messages.
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFA_LABEL: u16 = 3;
+const IFA_CACHEINFO: u16 = 6;
+const IFA_FLAGS: u16 = 8;
+
+/// The lifetime that the kernel reports for addresses that do not expire.
+const INFINITY_LIFE_TIME: u32 = 0xFFFF_FFFF;
+
+/// The longest link-layer address that the kernel supports (`MAX_ADDR_LEN`).
+const MAX_ADDR_LEN: usize = 32;
+
+bitflags! {
+    /// Represents a set of flags that describe an interface address.  This corresponds to the
+    /// `IFA_F_*` flags in `ifa_flags` and the `IFA_FLAGS` attribute.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct AddressFlags: u32 {
+        /// The address is a secondary address (IPv4), or a temporary address (IPv6).
+        const IFA_F_SECONDARY = 0x01;
+
+        /// The address is a temporary address (RFC 4941 privacy extensions).
+        const IFA_F_TEMPORARY = 0x01;
+
+        /// Duplicate address detection is not performed for this address.
+        const IFA_F_NODAD = 0x02;
+
+        /// The address may be used before duplicate address detection completes (RFC 4429).
+        const IFA_F_OPTIMISTIC = 0x04;
+
+        /// Duplicate address detection failed; the address is not used.
+        const IFA_F_DADFAILED = 0x08;
+
+        /// The address is a home address (Mobile IPv6).
+        const IFA_F_HOMEADDRESS = 0x10;
+
+        /// The preferred lifetime has expired; the address should not be used for new
+        /// connections.
+        const IFA_F_DEPRECATED = 0x20;
+
+        /// Duplicate address detection is still in progress; the address is not used yet.
+        const IFA_F_TENTATIVE = 0x40;
+
+        /// The address was configured statically, rather than by autoconfiguration.
+        const IFA_F_PERMANENT = 0x80;
+
+        /// Temporary addresses are created from the prefix of this address.
+        const IFA_F_MANAGETEMPADDR = 0x100;
+
+        /// No prefix route is created for this address.
+        const IFA_F_NOPREFIXROUTE = 0x200;
+
+        /// The multicast group of this address is joined automatically.
+        const IFA_F_MCAUTOJOIN = 0x400;
+
+        /// The address was generated with stable privacy (RFC 7217).
+        const IFA_F_STABLE_PRIVACY = 0x800;
+    }
+}
+
+/// The remaining lifetimes of an address.  `None` means that the lifetime is infinite.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct AddressLifetimes {
+    /// How long the address may still be used for new connections.
+    pub preferred: Option<Duration>,
+
+    /// How long the address remains configured.
+    pub valid: Option<Duration>,
+}
+
+const ARPHRD_ETHER: u16 = 1;
+const ARPHRD_IEEE802: u16 = 6;
+const ARPHRD_IEEE1394: u16 = 24;
+const ARPHRD_INFINIBAND: u16 = 32;
+const ARPHRD_CAN: u16 = 280;
+const ARPHRD_PPP: u16 = 512;
+const ARPHRD_RAWIP: u16 = 519;
+const ARPHRD_TUNNEL: u16 = 768;
+const ARPHRD_TUNNEL6: u16 = 769;
+const ARPHRD_LOOPBACK: u16 = 772;
+const ARPHRD_SIT: u16 = 776;
+const ARPHRD_IPGRE: u16 = 778;
+const ARPHRD_IEEE80211: u16 = 801;
+const ARPHRD_IEEE80211_RADIOTAP: u16 = 803;
+const ARPHRD_IP6GRE: u16 = 823;
+const ARPHRD_NONE: u16 = 0xFFFE;
+const ARPHRD_VOID: u16 = 0xFFFF;
+
+/// The hardware type of a link (`ARPHRD_*`), which determines the format of its link-layer
+/// address.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum LinkType {
+    /// Ethernet, and links that use Ethernet addresses (e.g. veth, bridges, most virtual links).
+    Ether,
+
+    /// IEEE 802.2 (Token Ring).
+    Ieee802,
+
+    /// IEEE 1394 (FireWire), with 16-byte addresses.
+    Ieee1394,
+
+    /// InfiniBand (IP over InfiniBand), with 20-byte addresses.
+    Infiniband,
+
+    /// Controller Area Network, without addresses.
+    Can,
+
+    /// Point-to-point protocol, without addresses.
+    Ppp,
+
+    /// Raw IP, without addresses.
+    RawIp,
+
+    /// IPv4 in IPv4 tunnels, whose address is the local IPv4 endpoint.
+    Tunnel,
+
+    /// IPv6 tunnels, whose address is the local IPv6 endpoint.
+    Tunnel6,
+
+    /// The loopback interface.
+    Loopback,
+
+    /// IPv6 in IPv4 tunnels (SIT).
+    Sit,
+
+    /// GRE over IPv4 tunnels.
+    IpGre,
+
+    /// Wireless links.  The kernel only reports this type in monitor mode, with 802.11 headers;
+    /// `Interface::link_type` also returns it for wireless links that use Ethernet framing.
+    Ieee80211,
+
+    /// Wireless links in monitor mode, with radiotap headers.
+    Ieee80211Radiotap,
+
+    /// GRE over IPv6 tunnels.
+    Ip6Gre,
+
+    /// Links without a hardware header, like TUN devices.
+    None,
+
+    /// A link type that is not known yet.
+    Void,
+
+    /// A link type that is not known to this crate.  The interior value is the numerical type.
+    Other(u16),
+}
+
+impl LinkType {
+    pub(crate) fn from_raw(v: u16) -> LinkType {
+        match v {
+            ARPHRD_ETHER => LinkType::Ether,
+            ARPHRD_IEEE802 => LinkType::Ieee802,
+            ARPHRD_IEEE1394 => LinkType::Ieee1394,
+            ARPHRD_INFINIBAND => LinkType::Infiniband,
+            ARPHRD_CAN => LinkType::Can,
+            ARPHRD_PPP => LinkType::Ppp,
+            ARPHRD_RAWIP => LinkType::RawIp,
+            ARPHRD_TUNNEL => LinkType::Tunnel,
+            ARPHRD_TUNNEL6 => LinkType::Tunnel6,
+            ARPHRD_LOOPBACK => LinkType::Loopback,
+            ARPHRD_SIT => LinkType::Sit,
+            ARPHRD_IPGRE => LinkType::IpGre,
+            ARPHRD_IEEE80211 => LinkType::Ieee80211,
+            ARPHRD_IEEE80211_RADIOTAP => LinkType::Ieee80211Radiotap,
+            ARPHRD_IP6GRE => LinkType::Ip6Gre,
+            ARPHRD_NONE => LinkType::None,
+            ARPHRD_VOID => LinkType::Void,
+            v => LinkType::Other(v),
+        }
+    }
+
+    /// Returns the numerical `ARPHRD_*` value of this type.
+    pub fn to_raw(self) -> u16 {
+        match self {
+            LinkType::Ether => ARPHRD_ETHER,
+            LinkType::Ieee802 => ARPHRD_IEEE802,
+            LinkType::Ieee1394 => ARPHRD_IEEE1394,
+            LinkType::Infiniband => ARPHRD_INFINIBAND,
+            LinkType::Can => ARPHRD_CAN,
+            LinkType::Ppp => ARPHRD_PPP,
+            LinkType::RawIp => ARPHRD_RAWIP,
+            LinkType::Tunnel => ARPHRD_TUNNEL,
+            LinkType::Tunnel6 => ARPHRD_TUNNEL6,
+            LinkType::Loopback => ARPHRD_LOOPBACK,
+            LinkType::Sit => ARPHRD_SIT,
+            LinkType::IpGre => ARPHRD_IPGRE,
+            LinkType::Ieee80211 => ARPHRD_IEEE80211,
+            LinkType::Ieee80211Radiotap => ARPHRD_IEEE80211_RADIOTAP,
+            LinkType::Ip6Gre => ARPHRD_IP6GRE,
+            LinkType::None => ARPHRD_NONE,
+            LinkType::Void => ARPHRD_VOID,
+            LinkType::Other(v) => v,
+        }
+    }
+}
+
+impl fmt::Display for LinkType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LinkType::Ether => write!(f, "ether"),
+            LinkType::Ieee802 => write!(f, "ieee802"),
+            LinkType::Ieee1394 => write!(f, "ieee1394"),
+            LinkType::Infiniband => write!(f, "infiniband"),
+            LinkType::Can => write!(f, "can"),
+            LinkType::Ppp => write!(f, "ppp"),
+            LinkType::RawIp => write!(f, "rawip"),
+            LinkType::Tunnel => write!(f, "ipip"),
+            LinkType::Tunnel6 => write!(f, "tunnel6"),
+            LinkType::Loopback => write!(f, "loopback"),
+            LinkType::Sit => write!(f, "sit"),
+            LinkType::IpGre => write!(f, "gre"),
+            LinkType::Ieee80211 => write!(f, "ieee802.11"),
+            LinkType::Ieee80211Radiotap => write!(f, "ieee802.11/radiotap"),
+            LinkType::Ip6Gre => write!(f, "gre6"),
+            LinkType::None => write!(f, "none"),
+            LinkType::Void => write!(f, "void"),
+            LinkType::Other(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// The link-layer address of an interface, as reported in the `AddressMetadata::link` of
+/// `Kind::Packet` addresses.  This corresponds to `struct sockaddr_ll`.  Unlike `HardwareAddr`,
+/// the address keeps its true length, which depends on the type of the link.
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct LinkAddress {
+    /// The index of the interface.
+    pub ifindex: u32,
+
+    /// The hardware type, which determines the format of the address.
+    pub hardware_type: LinkType,
+
+    /// The packet type (`PACKET_*`).  This is `PACKET_HOST` (`0`) for interface addresses.
+    pub packet_type: u8,
+
+    // The link-layer address, padded with zeros.
+    address: [u8; MAX_ADDR_LEN],
+    len: u8,
+}
+
+impl LinkAddress {
+    /// Creates a new link-layer address.  Only the first 32 bytes of `address` are kept, which is
+    /// the longest address the kernel supports.
+    pub fn new(
+        ifindex: u32,
+        hardware_type: LinkType,
+        packet_type: u8,
+        address: &[u8],
+    ) -> LinkAddress {
+        let len = address.len().min(MAX_ADDR_LEN);
+        let mut buf = [0; MAX_ADDR_LEN];
+        buf[..len].copy_from_slice(&address[..len]);
+        LinkAddress {
+            ifindex,
+            hardware_type,
+            packet_type,
+            address: buf,
+            len: len as u8,
+        }
+    }
+
+    /// Returns the link-layer address.  Its length depends on the hardware type, e.g. six bytes
+    /// for Ethernet, twenty for InfiniBand, and none at all for TUN devices.
+    pub fn address(&self) -> &[u8] {
+        &self.address[..self.len as usize]
+    }
+
+    /// Returns the address as a `HardwareAddr`, if it is a six-byte (Ethernet-style) address.
+    pub fn hardware_addr(&self) -> Option<HardwareAddr> {
+        HardwareAddr::try_from(self).ok()
+    }
+}
+
+impl<'a> TryFrom<&'a LinkAddress> for HardwareAddr {
+    type Error = HardwareAddrError;
+
+    /// Converts a six-byte (Ethernet-style) link-layer address, regardless of its hardware type.
+    fn try_from(addr: &'a LinkAddress) -> ::std::result::Result<HardwareAddr, HardwareAddrError> {
+        HardwareAddr::try_from(addr.address())
+    }
+}
+
+impl fmt::Debug for LinkAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LinkAddress")
+            .field("ifindex", &self.ifindex)
+            .field("hardware_type", &self.hardware_type)
+            .field("packet_type", &self.packet_type)
+            .field("address", &self.address())
+            .finish()
+    }
+}
+
+impl From<HardwareAddr> for LinkAddress {
+    /// Returns the Ethernet address `addr`, not associated with any interface.
+    fn from(addr: HardwareAddr) -> LinkAddress {
+        LinkAddress::new(0, LinkType::Ether, 0, addr.as_bytes())
+    }
+}
+
+impl fmt::Display for LinkAddress {
+    /// Formats the address as hexadecimal octets, each separated by a colon.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, b) in self.address().iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// The metadata of one address, as parsed from an `RTM_NEWADDR` message.
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct AddressInfo {
+    ifindex: u32,
+    ip: net::IpAddr,
+    prefix_len: u8,
+    flags: AddressFlags,
+    scope: RouteScope,
+    lifetimes: Option<AddressLifetimes>,
+    label: Option<String>,
+}
+
+impl AddressInfo {
+    fn from_message(msg: &Message) -> Option<AddressInfo> {
+        if msg.payload.len() < IFADDRMSG_LEN {
+            return None;
+        }
+
+        let hdr = &msg.payload;
+        match hdr[0] as i32 {
+            libc::AF_INET | libc::AF_INET6 => {}
+            _ => return None,
+        }
+
+        let mut address = None;
+        let mut local = None;
+        let mut flags = hdr[2] as u32;
+        let mut lifetimes = None;
+        let mut label = None;
+        for (ty, val) in msg.attrs(IFADDRMSG_LEN) {
+            match ty {
+                IFA_ADDRESS => address = netlink::parse_ip(val),
+                IFA_LOCAL => local = netlink::parse_ip(val),
+                IFA_LABEL => label = netlink::parse_str(val),
+                IFA_FLAGS => flags = netlink::parse_u32(val)?,
+                IFA_CACHEINFO if val.len() >= 8 => {
+                    lifetimes = Some(AddressLifetimes {
+                        preferred: lifetime(netlink::parse_u32(&val[0..4])?),
+                        valid: lifetime(netlink::parse_u32(&val[4..8])?),
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        // `IFA_ADDRESS` is the peer address of point-to-point links, and `getifaddrs` reports
+        // `IFA_LOCAL` in that case.
+        Some(AddressInfo {
+            ifindex: netlink::parse_u32(&hdr[4..8])?,
+            ip: local.or(address)?,
+            prefix_len: hdr[1],
+            flags: AddressFlags::from_bits_truncate(flags),
+            scope: RouteScope::from_raw(hdr[3]),
+            lifetimes,
+            label,
+        })
+    }
+
+    /// Returns whether this is the metadata of `addr`.
+    fn matches(&self, ifindex: u32, addr: &Address) -> bool {
+        let prefix_len_matches = match addr.prefix_len() {
+            Ok(Some(len)) => len == self.prefix_len,
+            _ => true,
+        };
+        self.ifindex == ifindex && addr.ip == Some(self.ip) && prefix_len_matches
+    }
+}
+
+impl Address {
+    /// Returns whether this address can be used as the source of new connections, i.e. it is
+    /// neither tentative, deprecated nor a duplicate.
+    pub fn is_usable(&self) -> bool {
+        !self.metadata.flags.intersects(
+            AddressFlags::IFA_F_TENTATIVE
+                | AddressFlags::IFA_F_DEPRECATED
+                | AddressFlags::IFA_F_DADFAILED,
+        )
+    }
+
+    /// Returns the label of an IPv4 address.  This is the name of the interface, or the name of
+    /// the alias (e.g. `eth0:1`) that the address was added with.
+    pub fn label(&self) -> Option<&str> {
+        let label = &self.metadata.label[..self.metadata.label_len as usize];
+        match str::from_utf8(label) {
+            Ok(label) if !label.is_empty() => Some(label),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this is a secondary IPv4 address, i.e. there is another address in the same
+    /// subnet on the same interface that is used as the source instead.
+    pub fn is_secondary(&self) -> bool {
+        self.kind == Kind::Ipv4 && self.metadata.flags.contains(AddressFlags::IFA_F_SECONDARY)
+    }
+}
+
+impl Interface {
+    /// Retrieves the link-layer address of this interface, with its true length and hardware
+    /// type.  This is the address that was read together with the interface, if there is one;
+    /// otherwise, the kernel is asked for it.
+    pub fn link_address(&self) -> Result<LinkAddress> {
+        if let Some(link) = self.addresses.iter().find_map(|a| a.metadata.link) {
+            return Ok(link);
+        }
+
+        let info = self.link_info()?;
+        Ok(LinkAddress::new(
+            info.index,
+            info.link_type,
+            libc::PACKET_HOST,
+            &info.address,
+        ))
+    }
+}
+
+/// Fills the flags, scope, lifetimes and labels into the addresses of the given interfaces, from
+/// a single dump of all addresses.  The addresses are left as they are if rtnetlink is not
+/// available.
+///
+/// This relies on the link information filled in by `link::fill_link_info` for the index of each
+/// interface, so that no further requests are needed.
+pub(crate) fn fill_metadata(ifs: &mut [Interface]) -> Result<()> {
+    let infos = match dump_addresses() {
+        Ok(infos) => infos,
+        Err(ref e) if netlink::is_unavailable(e) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for iface in ifs.iter_mut() {
+        let ifindex = match iface.link {
+            Some(ref link) => link.index,
+            None => continue,
+        };
+
+        for addr in iface.addresses.iter_mut() {
+            if let Some(info) = infos.iter().find(|info| info.matches(ifindex, addr)) {
+                addr.metadata.flags = info.flags;
+                addr.metadata.scope = Some(info.scope);
+                addr.metadata.lifetimes = info.lifetimes;
+                if let Some(ref label) = info.label {
+                    // Labels are interface names, so they always fit.
+                    let len = label.len().min(addr.metadata.label.len());
+                    addr.metadata.label[..len].copy_from_slice(&label.as_bytes()[..len]);
+                    addr.metadata.label_len = len as u8;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the metadata of all addresses in the namespace of the calling thread.
+fn dump_addresses() -> Result<Vec<AddressInfo>> {
+    let mut req = Request::new(netlink::RTM_GETADDR, 0);
+    req.put_u8(libc::AF_UNSPEC as u8);
+    req.put_u8(0);
+    req.put_u8(0);
+    req.put_u8(0);
+    req.put_u32(0);
+
+    let mut sock = Socket::route()?;
+    let msgs = sock.dump(req)?;
+    Ok(msgs
+        .iter()
+        .filter(|msg| msg.ty == netlink::RTM_NEWADDR)
+        .filter_map(AddressInfo::from_message)
+        .collect())
+}
+
+fn lifetime(secs: u32) -> Option<Duration> {
+    if secs == INFINITY_LIFE_TIME {
+        None
+    } else {
+        Some(Duration::from_secs(secs as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netlink::{NLM_F_CREATE, NLM_F_EXCL};
+    use testutil;
+
+    fn add_ipv4(ifindex: u32, ip: &str, prefix_len: u8, label: &str) {
+        let mut req = Request::new(netlink::RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL);
+        req.put_u8(libc::AF_INET as u8);
+        req.put_u8(prefix_len);
+        req.put_u8(0);
+        req.put_u8(0);
+        req.put_u32(ifindex);
+        req.attr_ip(IFA_LOCAL, ip.parse().unwrap());
+        req.attr_str(IFA_LABEL, label);
+        Socket::route().unwrap().request(req).unwrap();
+    }
+
+    #[test]
+    fn test_from_message() {
+        let mut req = Request::new(netlink::RTM_NEWADDR, 0);
+        req.put_u8(libc::AF_INET6 as u8);
+        req.put_u8(64);
+        req.put_u8(0);
+        req.put_u8(253);
+        req.put_u32(3);
+        req.attr_ip(IFA_ADDRESS, "fe80::1".parse().unwrap());
+        req.attr_u32(IFA_FLAGS, 0x240);
+        let mut cacheinfo = vec![];
+        for v in &[1800, INFINITY_LIFE_TIME, 0, 0] {
+            cacheinfo.extend_from_slice(&u32::to_ne_bytes(*v));
+        }
+        req.attr(IFA_CACHEINFO, &cacheinfo);
+
+        let msg = Message {
+            ty: netlink::RTM_NEWADDR,
+            payload: req.body().to_vec(),
+        };
+        let info = AddressInfo::from_message(&msg).unwrap();
+        assert_eq!(info.ifindex, 3);
+        assert_eq!(info.ip, "fe80::1".parse::<net::IpAddr>().unwrap());
+        assert_eq!(info.prefix_len, 64);
+        assert_eq!(info.scope, RouteScope::Link);
+        assert_eq!(
+            info.flags,
+            AddressFlags::IFA_F_TENTATIVE | AddressFlags::IFA_F_NOPREFIXROUTE
+        );
+        assert_eq!(
+            info.lifetimes,
+            Some(AddressLifetimes {
+                preferred: Some(Duration::from_secs(1800)),
+                valid: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_address_metadata() {
+        testutil::in_netns(|| {
+            let mut lo = Interface::get_by_name("lo").unwrap().unwrap();
+            lo.set_up(true).unwrap();
+            let (mut veth, mut peer) = Interface::create_veth("veth0", "veth1").unwrap();
+            veth.set_up(true).unwrap();
+            peer.set_up(true).unwrap();
+
+            let lo = Interface::get_by_name("lo").unwrap().unwrap();
+            let addr = lo.addresses.iter().find(|a| a.kind == Kind::Ipv4).unwrap();
+            assert_eq!(addr.metadata.scope, Some(RouteScope::Host));
+            assert!(addr.metadata.flags.contains(AddressFlags::IFA_F_PERMANENT));
+            assert!(addr.is_usable());
+            let lifetimes = addr.metadata.lifetimes.unwrap();
+            assert_eq!(lifetimes.preferred, None);
+            assert_eq!(lifetimes.valid, None);
+
+            // The link-local address is tentative until duplicate address detection completes,
+            // and has a link scope either way.  By default, it is derived with EUI-64.
+            let veth = Interface::get_by_name("veth0").unwrap().unwrap();
+            let addr = veth.addresses.iter().find(|a| a.kind == Kind::Ipv6);
+            if let Some(addr) = addr {
+                let hw = veth.hardware_addr().unwrap();
+                assert_eq!(addr.ip, Some(net::IpAddr::V6(hw.to_ipv6_link_local())));
+                assert_eq!(addr.metadata.scope, Some(RouteScope::Link));
+                assert!(!addr.metadata.flags.contains(AddressFlags::IFA_F_DADFAILED));
+            }
+        });
+    }
+
+    #[test]
+    fn test_aliases() {
+        testutil::in_netns(|| {
+            let (veth, _) = Interface::create_veth("veth0", "veth1").unwrap();
+            let index = veth.index().unwrap();
+            add_ipv4(index, "10.1.0.1", 24, "veth0");
+            add_ipv4(index, "10.1.0.2", 24, "veth0:1");
+
+            let veth = Interface::get_by_name("veth0").unwrap().unwrap();
+            let mut addrs: Vec<&Address> = veth
+                .addresses
+                .iter()
+                .filter(|a| a.kind == Kind::Ipv4)
+                .collect();
+            addrs.sort_by_key(|a| a.ip);
+            assert_eq!(addrs.len(), 2);
+            assert_eq!(addrs[0].label(), Some("veth0"));
+            assert!(!addrs[0].is_secondary());
+            assert_eq!(addrs[1].label(), Some("veth0:1"));
+            assert!(addrs[1].is_secondary());
+
+            let all = Interface::get_all().unwrap();
+            assert!(all.iter().all(|i| i.name != "veth0:1"));
+
+            let all = Interface::get_all_with_aliases().unwrap();
+            let alias = all.iter().find(|i| i.name == "veth0:1").unwrap();
+            assert_eq!(alias.addresses.len(), 1);
+            assert!(alias.addresses[0].is_secondary());
+
+            let alias = Interface::get_by_name("veth0:1").unwrap().unwrap();
+            assert_eq!(alias.addresses.len(), 1);
+
+            // The same address with another prefix length has a label of its own, which is kept
+            // in copies of the address.
+            add_ipv4(index, "10.2.0.1", 24, "veth0:2");
+            add_ipv4(index, "10.2.0.1", 16, "veth0:3");
+            let addrs: Vec<Address> = Interface::get_by_name("veth0")
+                .unwrap()
+                .unwrap()
+                .addresses
+                .iter()
+                .filter(|a| a.ip == Some("10.2.0.1".parse().unwrap()))
+                .cloned()
+                .collect();
+            assert_eq!(addrs.len(), 2);
+            for addr in addrs.iter() {
+                match addr.prefix_len().unwrap() {
+                    Some(24) => assert_eq!(addr.label(), Some("veth0:2")),
+                    Some(16) => assert_eq!(addr.label(), Some("veth0:3")),
+                    len => panic!("unexpected prefix length {:?}", len),
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_link_address() {
+        testutil::in_netns(|| {
+            let (veth, _) = Interface::create_veth("veth0", "veth1").unwrap();
+            let link = veth
+                .addresses
+                .iter()
+                .filter_map(|a| a.metadata.link)
+                .next()
+                .unwrap();
+            assert_eq!(link.ifindex, veth.index().unwrap());
+            assert_eq!(link.hardware_type, LinkType::Ether);
+            assert_eq!(link.packet_type, libc::PACKET_HOST);
+            assert_eq!(veth.link_address().unwrap(), link);
+            assert_eq!(link.address(), veth.hardware_addr().unwrap().as_bytes());
+
+            let lo = Interface::get_by_name("lo").unwrap().unwrap();
+            let addr = lo
+                .addresses
+                .iter()
+                .find(|a| a.kind == Kind::Packet)
+                .unwrap();
+            let link = addr.metadata.link.unwrap();
+            assert_eq!(link.hardware_type, LinkType::Loopback);
+            assert_eq!(link.address(), &[0; 6]);
+            assert!(addr.ip.is_none());
+        });
+    }
+
+    #[test]
+    fn test_link_type() {
+        for v in &[1, 32, 772, 0xFFFE, 1234] {
+            assert_eq!(LinkType::from_raw(*v).to_raw(), *v);
+        }
+        assert_eq!(LinkType::from_raw(32), LinkType::Infiniband);
+    }
+
+    #[test]
+    fn test_link_address_conversions() {
+        let hw = HardwareAddr([0x02, 0, 0, 0, 0, 0x01]);
+        let link = LinkAddress::from(hw);
+        assert_eq!(link.hardware_type, LinkType::Ether);
+        assert_eq!(link.to_string(), "02:00:00:00:00:01");
+        assert_eq!(link.hardware_addr(), Some(hw));
+
+        let bytes: Vec<u8> = (0..40).collect();
+        let ib = LinkAddress::new(1, LinkType::Infiniband, 0, &bytes[..20]);
+        assert_eq!(ib.address(), &bytes[..20]);
+        assert_eq!(ib.hardware_addr(), None);
+        assert_eq!(
+            HardwareAddr::try_from(&ib),
+            Err(HardwareAddrError::InvalidLength(20))
+        );
+        assert_eq!(HardwareAddr::try_from(&link), Ok(hw));
+        assert_eq!(ib.to_string().len(), 20 * 3 - 1);
+
+        let long = LinkAddress::new(1, LinkType::Other(1234), 0, &bytes);
+        assert_eq!(long.address(), &bytes[..MAX_ADDR_LEN]);
+    }
+}
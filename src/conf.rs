@@ -0,0 +1,636 @@
+//! Per-interface IPv4 and IPv6 settings, as found under `/proc/sys/net/ipv{4,6}/conf/<name>`.
+//! Each setting is read and written with a typed getter and setter:
+//!
+//! ```no_run
+//! # use interfaces::Interface;
+//! # use interfaces::conf::AcceptRa;
+//! let iface = Interface::get_by_name("eth0").unwrap().unwrap();
+//! iface.ipv6_conf().set_accept_ra(AcceptRa::Always).unwrap();
+//! ```
+//!
+//! Besides real interfaces, the `all` pseudo-interface changes a setting on every interface at
+//! once (or, for some settings, is combined with the per-interface value), and `default` holds
+//! the settings that new interfaces start out with.  This is only available on Linux.
+
+use std::fmt;
+
+use nix::errno::Errno;
+
+use netns::Netns;
+use sysctl;
+use {Interface, InterfacesError, Result};
+
+/// The name of the pseudo-interface that applies to all interfaces.
+const ALL: &str = "all";
+
+/// The name of the pseudo-interface that holds the settings of new interfaces.
+const DEFAULT: &str = "default";
+
+/// A value that a setting can hold, stored as an integer.
+trait ConfValue: Sized {
+    fn from_raw(v: i64) -> Option<Self>;
+    fn to_raw(&self) -> i64;
+}
+
+impl ConfValue for bool {
+    fn from_raw(v: i64) -> Option<bool> {
+        Some(v != 0)
+    }
+
+    fn to_raw(&self) -> i64 {
+        *self as i64
+    }
+}
+
+/// The reverse path filtering mode of an interface (`rp_filter`).
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RpFilter {
+    /// No source validation.
+    Off,
+
+    /// Packets are dropped unless the route back to their source goes out of the interface they
+    /// arrived on (RFC 3704 strict mode).
+    Strict,
+
+    /// Packets are dropped unless their source is reachable through any interface (RFC 3704
+    /// loose mode).  The kernel treats every value other than `0` and `1` like this, so they are
+    /// read as `Loose` too.
+    Loose,
+}
+
+impl ConfValue for RpFilter {
+    fn from_raw(v: i64) -> Option<RpFilter> {
+        match v {
+            0 => Some(RpFilter::Off),
+            1 => Some(RpFilter::Strict),
+            _ => Some(RpFilter::Loose),
+        }
+    }
+
+    fn to_raw(&self) -> i64 {
+        match *self {
+            RpFilter::Off => 0,
+            RpFilter::Strict => 1,
+            RpFilter::Loose => 2,
+        }
+    }
+}
+
+/// Which ARP requests an interface replies to (`arp_ignore`).
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ArpIgnore {
+    /// Reply for any local address, configured on any interface.  The reserved values `4` to `7`
+    /// and other unknown values behave like this in the kernel, so they are read as `Any` too.
+    Any,
+
+    /// Only reply if the target address is configured on the incoming interface.
+    TargetOnInterface,
+
+    /// Only reply if the target address is configured on the incoming interface, and the sender
+    /// is in the same subnet as it.
+    SenderInSubnet,
+
+    /// Do not reply for addresses with host scope; only those with global and link scope.
+    NoHostScope,
+
+    /// Never reply.
+    Never,
+}
+
+impl ConfValue for ArpIgnore {
+    fn from_raw(v: i64) -> Option<ArpIgnore> {
+        match v {
+            0 => Some(ArpIgnore::Any),
+            1 => Some(ArpIgnore::TargetOnInterface),
+            2 => Some(ArpIgnore::SenderInSubnet),
+            3 => Some(ArpIgnore::NoHostScope),
+            8 => Some(ArpIgnore::Never),
+            _ => Some(ArpIgnore::Any),
+        }
+    }
+
+    fn to_raw(&self) -> i64 {
+        match *self {
+            ArpIgnore::Any => 0,
+            ArpIgnore::TargetOnInterface => 1,
+            ArpIgnore::SenderInSubnet => 2,
+            ArpIgnore::NoHostScope => 3,
+            ArpIgnore::Never => 8,
+        }
+    }
+}
+
+/// Which source address an interface announces in its own ARP requests (`arp_announce`).
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ArpAnnounce {
+    /// Use any local address, usually the source address of the packet that triggered the
+    /// request.  Unknown values behave like this in the kernel, so they are read as `Any` too.
+    Any,
+
+    /// Prefer an address in the subnet of the target.
+    SameSubnet,
+
+    /// Always use the best local address for the target, ignoring the source of the packet.
+    Best,
+}
+
+impl ConfValue for ArpAnnounce {
+    fn from_raw(v: i64) -> Option<ArpAnnounce> {
+        match v {
+            1 => Some(ArpAnnounce::SameSubnet),
+            2 => Some(ArpAnnounce::Best),
+            _ => Some(ArpAnnounce::Any),
+        }
+    }
+
+    fn to_raw(&self) -> i64 {
+        match *self {
+            ArpAnnounce::Any => 0,
+            ArpAnnounce::SameSubnet => 1,
+            ArpAnnounce::Best => 2,
+        }
+    }
+}
+
+/// Whether an interface accepts IPv6 router advertisements (`accept_ra`).
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AcceptRa {
+    /// Never accept router advertisements.
+    Never,
+
+    /// Accept router advertisements unless forwarding is enabled.  The kernel treats every value
+    /// other than `0` and `2` like this, so they are read as `IfNotForwarding` too.
+    IfNotForwarding,
+
+    /// Accept router advertisements, even if forwarding is enabled.
+    Always,
+}
+
+impl ConfValue for AcceptRa {
+    fn from_raw(v: i64) -> Option<AcceptRa> {
+        match v {
+            0 => Some(AcceptRa::Never),
+            2 => Some(AcceptRa::Always),
+            _ => Some(AcceptRa::IfNotForwarding),
+        }
+    }
+
+    fn to_raw(&self) -> i64 {
+        match *self {
+            AcceptRa::Never => 0,
+            AcceptRa::IfNotForwarding => 1,
+            AcceptRa::Always => 2,
+        }
+    }
+}
+
+/// Whether an interface uses temporary IPv6 addresses (RFC 4941 privacy extensions,
+/// `use_tempaddr`).
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum UseTempaddr {
+    /// No temporary addresses are generated.  Point-to-point and loopback interfaces report this
+    /// as `-1`; like the kernel, all values below `1` are read as `Disabled`.
+    Disabled,
+
+    /// Temporary addresses are generated, but public addresses are preferred as source.
+    Enabled,
+
+    /// Temporary addresses are generated, and preferred as source.  Like the kernel, all values
+    /// above `2` are read as `Preferred` too.
+    Preferred,
+}
+
+impl ConfValue for UseTempaddr {
+    fn from_raw(v: i64) -> Option<UseTempaddr> {
+        match v {
+            v if v < 1 => Some(UseTempaddr::Disabled),
+            1 => Some(UseTempaddr::Enabled),
+            _ => Some(UseTempaddr::Preferred),
+        }
+    }
+
+    fn to_raw(&self) -> i64 {
+        match *self {
+            UseTempaddr::Disabled => 0,
+            UseTempaddr::Enabled => 1,
+            UseTempaddr::Preferred => 2,
+        }
+    }
+}
+
+/// How an interface generates its IPv6 link-local and SLAAC addresses (`addr_gen_mode`).
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AddrGenMode {
+    /// The interface identifier is derived from the hardware address (modified EUI-64).
+    Eui64,
+
+    /// No link-local address is generated.
+    None,
+
+    /// The interface identifier is stable, but does not reveal the hardware address (RFC 7217).
+    /// This requires `stable_secret` to be set.
+    StablePrivacy,
+
+    /// Like `StablePrivacy`, with a random secret.
+    Random,
+}
+
+impl ConfValue for AddrGenMode {
+    fn from_raw(v: i64) -> Option<AddrGenMode> {
+        match v {
+            0 => Some(AddrGenMode::Eui64),
+            1 => Some(AddrGenMode::None),
+            2 => Some(AddrGenMode::StablePrivacy),
+            3 => Some(AddrGenMode::Random),
+            _ => None,
+        }
+    }
+
+    fn to_raw(&self) -> i64 {
+        match *self {
+            AddrGenMode::Eui64 => 0,
+            AddrGenMode::None => 1,
+            AddrGenMode::StablePrivacy => 2,
+            AddrGenMode::Random => 3,
+        }
+    }
+}
+
+/// The settings of one interface (or pseudo-interface) for one protocol.
+#[derive(Debug, Clone)]
+struct Conf {
+    proto: &'static str,
+    name: String,
+    netns: Option<Netns>,
+}
+
+impl Conf {
+    fn get<T: ConfValue>(&self, key: &str) -> Result<T> {
+        let v = self.run(|| sysctl::read_int(self.proto, &self.name, key))?;
+        T::from_raw(v).ok_or(InterfacesError::Errno(Errno::EINVAL))
+    }
+
+    fn set<T: ConfValue>(&self, key: &str, value: T) -> Result<()> {
+        let value = value.to_raw().to_string();
+        self.run(|| sysctl::write(self.proto, &self.name, key, &value))
+    }
+
+    /// Returns the name as it appears in sysctl(8) keys, where the dots of names like
+    /// `eth0.100` are written as slashes, so that they are not taken as separators.
+    fn key_name(&self) -> String {
+        self.name.replace('.', "/")
+    }
+
+    fn run<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T> + Send,
+        T: Send,
+    {
+        match self.netns {
+            Some(ref netns) => netns.run(f)?,
+            None => f(),
+        }
+    }
+}
+
+/// The IPv4 settings of an interface (`/proc/sys/net/ipv4/conf/<name>`).
+#[derive(Debug, Clone)]
+pub struct Ipv4Conf(Conf);
+
+impl Ipv4Conf {
+    fn new(name: &str, netns: Option<Netns>) -> Ipv4Conf {
+        Ipv4Conf(Conf {
+            proto: "ipv4",
+            name: name.to_string(),
+            netns,
+        })
+    }
+
+    /// Returns the settings of the `all` pseudo-interface, in the namespace of the calling
+    /// thread.
+    pub fn all() -> Ipv4Conf {
+        Ipv4Conf::new(ALL, None)
+    }
+
+    /// Returns the settings that new interfaces start out with, in the namespace of the calling
+    /// thread.
+    pub fn defaults() -> Ipv4Conf {
+        Ipv4Conf::new(DEFAULT, None)
+    }
+
+    /// Like `all`, but in the given namespace.
+    pub fn all_in_netns(netns: &Netns) -> Ipv4Conf {
+        Ipv4Conf::new(ALL, Some(netns.clone()))
+    }
+
+    /// Like `defaults`, but in the given namespace.
+    pub fn defaults_in_netns(netns: &Netns) -> Ipv4Conf {
+        Ipv4Conf::new(DEFAULT, Some(netns.clone()))
+    }
+
+    /// Returns the name of the interface (or pseudo-interface) these settings belong to.
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Returns whether packets are forwarded between this and other interfaces.
+    pub fn forwarding(&self) -> Result<bool> {
+        self.0.get("forwarding")
+    }
+
+    /// Enables or disables forwarding.
+    pub fn set_forwarding(&self, enabled: bool) -> Result<()> {
+        self.0.set("forwarding", enabled)
+    }
+
+    /// Returns the reverse path filtering mode.  The kernel uses the higher of this and the mode
+    /// of `all`, so `Loose` in either of them takes precedence over `Strict`.
+    pub fn rp_filter(&self) -> Result<RpFilter> {
+        self.0.get("rp_filter")
+    }
+
+    /// Sets the reverse path filtering mode.
+    pub fn set_rp_filter(&self, mode: RpFilter) -> Result<()> {
+        self.0.set("rp_filter", mode)
+    }
+
+    /// Returns which ARP requests are replied to.
+    pub fn arp_ignore(&self) -> Result<ArpIgnore> {
+        self.0.get("arp_ignore")
+    }
+
+    /// Sets which ARP requests are replied to.
+    pub fn set_arp_ignore(&self, mode: ArpIgnore) -> Result<()> {
+        self.0.set("arp_ignore", mode)
+    }
+
+    /// Returns which source address is announced in ARP requests.
+    pub fn arp_announce(&self) -> Result<ArpAnnounce> {
+        self.0.get("arp_announce")
+    }
+
+    /// Sets which source address is announced in ARP requests.
+    pub fn set_arp_announce(&self, mode: ArpAnnounce) -> Result<()> {
+        self.0.set("arp_announce", mode)
+    }
+
+    /// Returns whether ARP requests are answered for addresses that are routed through another
+    /// interface.
+    pub fn proxy_arp(&self) -> Result<bool> {
+        self.0.get("proxy_arp")
+    }
+
+    /// Enables or disables proxy ARP.
+    pub fn set_proxy_arp(&self, enabled: bool) -> Result<()> {
+        self.0.set("proxy_arp", enabled)
+    }
+}
+
+/// The IPv6 settings of an interface (`/proc/sys/net/ipv6/conf/<name>`).
+#[derive(Debug, Clone)]
+pub struct Ipv6Conf(Conf);
+
+impl Ipv6Conf {
+    fn new(name: &str, netns: Option<Netns>) -> Ipv6Conf {
+        Ipv6Conf(Conf {
+            proto: "ipv6",
+            name: name.to_string(),
+            netns,
+        })
+    }
+
+    /// Returns the settings of the `all` pseudo-interface, in the namespace of the calling
+    /// thread.
+    pub fn all() -> Ipv6Conf {
+        Ipv6Conf::new(ALL, None)
+    }
+
+    /// Returns the settings that new interfaces start out with, in the namespace of the calling
+    /// thread.
+    pub fn defaults() -> Ipv6Conf {
+        Ipv6Conf::new(DEFAULT, None)
+    }
+
+    /// Like `all`, but in the given namespace.
+    pub fn all_in_netns(netns: &Netns) -> Ipv6Conf {
+        Ipv6Conf::new(ALL, Some(netns.clone()))
+    }
+
+    /// Like `defaults`, but in the given namespace.
+    pub fn defaults_in_netns(netns: &Netns) -> Ipv6Conf {
+        Ipv6Conf::new(DEFAULT, Some(netns.clone()))
+    }
+
+    /// Returns the name of the interface (or pseudo-interface) these settings belong to.
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Returns whether packets are forwarded between this and other interfaces, i.e. whether the
+    /// interface acts as a router.
+    pub fn forwarding(&self) -> Result<bool> {
+        self.0.get("forwarding")
+    }
+
+    /// Enables or disables forwarding.
+    pub fn set_forwarding(&self, enabled: bool) -> Result<()> {
+        self.0.set("forwarding", enabled)
+    }
+
+    /// Returns whether router advertisements are accepted.
+    pub fn accept_ra(&self) -> Result<AcceptRa> {
+        self.0.get("accept_ra")
+    }
+
+    /// Sets whether router advertisements are accepted.
+    pub fn set_accept_ra(&self, mode: AcceptRa) -> Result<()> {
+        self.0.set("accept_ra", mode)
+    }
+
+    /// Returns whether IPv6 is disabled.
+    pub fn disable_ipv6(&self) -> Result<bool> {
+        self.0.get("disable_ipv6")
+    }
+
+    /// Disables or enables IPv6.  Disabling it removes all IPv6 addresses.
+    pub fn set_disable_ipv6(&self, disabled: bool) -> Result<()> {
+        self.0.set("disable_ipv6", disabled)
+    }
+
+    /// Returns whether addresses are configured from the prefixes in router advertisements
+    /// (SLAAC).
+    pub fn autoconf(&self) -> Result<bool> {
+        self.0.get("autoconf")
+    }
+
+    /// Enables or disables address autoconfiguration.
+    pub fn set_autoconf(&self, enabled: bool) -> Result<()> {
+        self.0.set("autoconf", enabled)
+    }
+
+    /// Returns whether temporary addresses are used.
+    pub fn use_tempaddr(&self) -> Result<UseTempaddr> {
+        self.0.get("use_tempaddr")
+    }
+
+    /// Sets whether temporary addresses are used.
+    pub fn set_use_tempaddr(&self, mode: UseTempaddr) -> Result<()> {
+        self.0.set("use_tempaddr", mode)
+    }
+
+    /// Returns how link-local and SLAAC addresses are generated.
+    pub fn addr_gen_mode(&self) -> Result<AddrGenMode> {
+        self.0.get("addr_gen_mode")
+    }
+
+    /// Sets how link-local and SLAAC addresses are generated.
+    pub fn set_addr_gen_mode(&self, mode: AddrGenMode) -> Result<()> {
+        self.0.set("addr_gen_mode", mode)
+    }
+
+    /// Returns whether neighbor solicitations are answered for the addresses of proxy entries.
+    pub fn proxy_ndp(&self) -> Result<bool> {
+        self.0.get("proxy_ndp")
+    }
+
+    /// Enables or disables proxy NDP.
+    pub fn set_proxy_ndp(&self, enabled: bool) -> Result<()> {
+        self.0.set("proxy_ndp", enabled)
+    }
+}
+
+impl fmt::Display for Ipv4Conf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "net.ipv4.conf.{}", self.0.key_name())
+    }
+}
+
+impl fmt::Display for Ipv6Conf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "net.ipv6.conf.{}", self.0.key_name())
+    }
+}
+
+impl Interface {
+    /// Returns the IPv4 settings of this interface.
+    pub fn ipv4_conf(&self) -> Ipv4Conf {
+        Ipv4Conf::new(&self.name, self.netns.clone())
+    }
+
+    /// Returns the IPv6 settings of this interface.
+    pub fn ipv6_conf(&self) -> Ipv6Conf {
+        Ipv6Conf::new(&self.name, self.netns.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutil;
+
+    #[test]
+    fn test_conf_values() {
+        for v in &[0, 1, 2, 3, 8] {
+            assert_eq!(ArpIgnore::from_raw(*v).unwrap().to_raw(), *v);
+        }
+        assert_eq!(RpFilter::from_raw(3), Some(RpFilter::Loose));
+
+        // Values that the kernel accepts are read the way the kernel interprets them.
+        for v in &[4, 7, 9, -1] {
+            assert_eq!(ArpIgnore::from_raw(*v), Some(ArpIgnore::Any));
+        }
+        assert_eq!(ArpAnnounce::from_raw(3), Some(ArpAnnounce::Any));
+        assert_eq!(UseTempaddr::from_raw(-1), Some(UseTempaddr::Disabled));
+        assert_eq!(UseTempaddr::from_raw(3), Some(UseTempaddr::Preferred));
+        assert_eq!(AcceptRa::from_raw(3), Some(AcceptRa::IfNotForwarding));
+        assert_eq!(AddrGenMode::from_raw(4), None);
+        assert_eq!(bool::from_raw(2), Some(true));
+    }
+
+    #[test]
+    fn test_interface_conf() {
+        testutil::in_netns(|| {
+            let (veth, _) = Interface::create_veth("veth0", "veth1").unwrap();
+
+            let v4 = veth.ipv4_conf();
+            assert_eq!(v4.to_string(), "net.ipv4.conf.veth0");
+            assert!(!v4.forwarding().unwrap());
+            v4.set_forwarding(true).unwrap();
+            assert!(v4.forwarding().unwrap());
+            v4.set_rp_filter(RpFilter::Loose).unwrap();
+            assert_eq!(v4.rp_filter().unwrap(), RpFilter::Loose);
+            v4.set_arp_ignore(ArpIgnore::TargetOnInterface).unwrap();
+            assert_eq!(v4.arp_ignore().unwrap(), ArpIgnore::TargetOnInterface);
+            v4.set_arp_announce(ArpAnnounce::Best).unwrap();
+            assert_eq!(v4.arp_announce().unwrap(), ArpAnnounce::Best);
+
+            let v6 = veth.ipv6_conf();
+            v6.set_accept_ra(AcceptRa::Always).unwrap();
+            assert_eq!(v6.accept_ra().unwrap(), AcceptRa::Always);
+            v6.set_autoconf(false).unwrap();
+            assert!(!v6.autoconf().unwrap());
+            v6.set_use_tempaddr(UseTempaddr::Preferred).unwrap();
+            assert_eq!(v6.use_tempaddr().unwrap(), UseTempaddr::Preferred);
+            v6.set_addr_gen_mode(AddrGenMode::Random).unwrap();
+            assert_eq!(v6.addr_gen_mode().unwrap(), AddrGenMode::Random);
+            v6.set_disable_ipv6(true).unwrap();
+            assert!(v6.disable_ipv6().unwrap());
+        });
+    }
+
+    #[test]
+    fn test_dotted_interface_conf() {
+        testutil::in_netns(|| {
+            let (veth, _) = Interface::create_veth("veth0.100", "veth1").unwrap();
+
+            let v4 = veth.ipv4_conf();
+            assert_eq!(v4.to_string(), "net.ipv4.conf.veth0/100");
+            v4.set_rp_filter(RpFilter::Strict).unwrap();
+            assert_eq!(v4.rp_filter().unwrap(), RpFilter::Strict);
+
+            let v6 = veth.ipv6_conf();
+            v6.set_accept_ra(AcceptRa::Never).unwrap();
+            assert_eq!(v6.accept_ra().unwrap(), AcceptRa::Never);
+        });
+    }
+
+    #[test]
+    fn test_pseudo_interface_conf() {
+        testutil::in_netns(|| {
+            Ipv4Conf::all().set_forwarding(true).unwrap();
+            assert!(Ipv4Conf::all().forwarding().unwrap());
+
+            // New interfaces inherit the defaults.
+            Ipv6Conf::defaults().set_accept_ra(AcceptRa::Never).unwrap();
+            let (veth, _) = Interface::create_veth("veth0", "veth1").unwrap();
+            assert_eq!(veth.ipv6_conf().accept_ra().unwrap(), AcceptRa::Never);
+
+            // Values outside of the enums are read the way the kernel interprets them.
+            let v6 = veth.ipv6_conf();
+            sysctl::write("ipv6", "veth0", "use_tempaddr", "3").unwrap();
+            assert_eq!(v6.use_tempaddr().unwrap(), UseTempaddr::Preferred);
+
+            // The pseudo-interfaces of other namespaces are separate.
+            let netns = Netns::new().unwrap();
+            Ipv4Conf::all_in_netns(&netns)
+                .set_forwarding(false)
+                .unwrap();
+            assert!(!Ipv4Conf::all_in_netns(&netns).forwarding().unwrap());
+            assert!(Ipv4Conf::all().forwarding().unwrap());
+            Ipv6Conf::defaults_in_netns(&netns)
+                .set_accept_ra(AcceptRa::Always)
+                .unwrap();
+            assert_eq!(
+                Ipv6Conf::defaults_in_netns(&netns).accept_ra().unwrap(),
+                AcceptRa::Always
+            );
+            assert_eq!(Ipv6Conf::defaults().accept_ra().unwrap(), AcceptRa::Never);
+
+            let missing = Ipv4Conf::new("missing0", None);
+            match missing.forwarding() {
+                Err(InterfacesError::Errno(Errno::ENOENT)) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+        });
+    }
+}
@@ -15,7 +15,6 @@ use std::ptr;
     target_os = "illumos",
     target_os = "vxworks",
     target_os = "wasi",
-    target_env = "wasi",
 ))]
 pub type ConstantType = libc::c_int;
 
@@ -90,7 +89,7 @@ lazy_static! {
 
 pub fn get_constant<S: AsRef<str>>(name: S) -> Option<ConstantType> {
     // Since `u64` is `Copy`, we can dereference the constant directly
-    CONSTANTS.get(name.as_ref()).map(|v| *v)
+    CONSTANTS.get(name.as_ref()).copied()
 }
 
 #[cfg(test)]
@@ -1,6 +1,7 @@
 use std::convert::From;
 use std::error::Error;
 use std::fmt;
+use std::io;
 
 use nix;
 
@@ -21,7 +22,7 @@ impl InterfacesError {
     /// Create a new instance of `InterfacesError` with the error set to the current value of the
     /// libc `errno` variable.
     pub fn last_os_error() -> InterfacesError {
-        return InterfacesError::Errno(nix::errno::Errno::last());
+        InterfacesError::Errno(nix::errno::Errno::last())
     }
 }
 
@@ -31,6 +32,15 @@ impl From<nix::errno::Errno> for InterfacesError {
     }
 }
 
+impl From<io::Error> for InterfacesError {
+    fn from(e: io::Error) -> InterfacesError {
+        match e.raw_os_error() {
+            Some(errno) => InterfacesError::Errno(nix::errno::Errno::from_i32(errno)),
+            None => InterfacesError::NotSupported("Unknown I/O error"),
+        }
+    }
+}
+
 impl Error for InterfacesError {
     fn description(&self) -> &str {
         use InterfacesError::*;
@@ -53,6 +63,46 @@ impl fmt::Display for InterfacesError {
     }
 }
 
+/// The error returned when a `HardwareAddr` cannot be parsed from a string, created from a slice
+/// of bytes, or generated with a given OUI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HardwareAddrError {
+    /// The slice of bytes has the given length instead of six.
+    InvalidLength(usize),
+
+    /// The string contains a character that is neither a hexadecimal digit nor a separator.
+    InvalidCharacter(char),
+
+    /// The string is not in one of the supported formats, e.g. it has the wrong number of digits
+    /// or mixes separators.
+    InvalidFormat,
+
+    /// The OUI cannot be used to generate addresses, because it is universally administered or
+    /// multicast.
+    InvalidOui([u8; 3]),
+}
+
+impl Error for HardwareAddrError {}
+
+impl fmt::Display for HardwareAddrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HardwareAddrError::InvalidLength(len) => {
+                write!(f, "invalid hardware address length {}, expected 6", len)
+            }
+            HardwareAddrError::InvalidCharacter(c) => {
+                write!(f, "invalid character {:?} in hardware address", c)
+            }
+            HardwareAddrError::InvalidFormat => write!(f, "invalid hardware address format"),
+            HardwareAddrError::InvalidOui(oui) => write!(
+                f,
+                "OUI {:02x}:{:02x}:{:02x} is not locally administered and unicast",
+                oui[0], oui[1], oui[2]
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::error::Error;
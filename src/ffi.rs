@@ -2,18 +2,16 @@
 
 use std::mem;
 use std::net;
-use std::ptr;
 
 use libc::{self, c_void, c_char, c_int, c_uint, c_ushort};
+#[cfg(target_os = "linux")]
+use libc::c_short;
 use nix::sys::socket;
 
-pub const IFNAMSIZ: usize = 16;
+#[cfg(target_os = "linux")]
+use address::{LinkAddress, LinkType};
 
-#[repr(C)]
-pub struct ifreq_with_hwaddr {
-    pub ifr_name: [u8; IFNAMSIZ],
-    pub ifr_hwaddr: socket::sockaddr,
-}
+pub const IFNAMSIZ: usize = 16;
 
 #[repr(C)]
 pub struct ifreq_with_flags {
@@ -27,6 +25,24 @@ pub struct ifreq_with_mtu {
     pub ifr_mtu: c_int,
 }
 
+#[cfg(target_os = "linux")]
+#[repr(C)]
+pub struct ifreq_with_ifindex {
+    pub ifr_name: [u8; IFNAMSIZ],
+    pub ifr_ifindex: c_int,
+    // The kernel copies a full `struct ifreq` back out, so leave room for the rest of the union.
+    pub ifr_pad: [u8; 20],
+}
+
+#[cfg(target_os = "linux")]
+#[repr(C)]
+pub struct ifreq_with_tun_flags {
+    pub ifr_name: [u8; IFNAMSIZ],
+    pub ifr_flags: c_short,
+    // As above, the kernel writes a full `struct ifreq` back out.
+    pub ifr_pad: [u8; 22],
+}
+
 #[repr(C)]
 pub struct union_ifa_ifu {
     pub data: *mut c_void,
@@ -56,12 +72,47 @@ extern "C" {
     pub fn freeifaddrs(ifa: *mut ifaddrs) -> c_void;
 }
 
+// `getifaddrs` leaves room for link-layer addresses of up to this many bytes, even though
+// `sockaddr_ll` itself only has room for eight.
+#[cfg(target_os = "linux")]
+const SOCKADDR_LL_MAX_ADDR: usize = 24;
+
+// The `sockaddr_ll` that `getifaddrs` allocates for each link, like glibc's `sockaddr_ll_max`.
+// Reading the longer addresses through `libc::sockaddr_ll` would go past its `sll_addr`.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct sockaddr_ll_max {
+    sll_family: c_ushort,
+    sll_protocol: c_ushort,
+    sll_ifindex: c_int,
+    sll_hatype: c_ushort,
+    sll_pkttype: u8,
+    sll_halen: u8,
+    sll_addr: [u8; SOCKADDR_LL_MAX_ADDR],
+}
+
+#[cfg(target_os = "linux")]
+pub fn convert_sockaddr_ll(sa: *mut socket::sockaddr) -> Option<LinkAddress> {
+    if sa.is_null() || unsafe { *sa }.sa_family as i32 != libc::AF_PACKET {
+        return None;
+    }
+
+    let sa = unsafe { &*(sa as *const sockaddr_ll_max) };
+    let len = (sa.sll_halen as usize).min(SOCKADDR_LL_MAX_ADDR);
+    Some(LinkAddress::new(
+        sa.sll_ifindex as u32,
+        LinkType::from_raw(sa.sll_hatype),
+        sa.sll_pkttype,
+        &sa.sll_addr[..len],
+    ))
+}
+
 fn make_int16(hi: u8, lo: u8) -> u16 {
     (lo as u16) | ((hi as u16) << 8)
 }
 
 pub fn convert_sockaddr(sa: *mut socket::sockaddr) -> Option<net::SocketAddr> {
-    if sa == ptr::null_mut() {
+    if sa.is_null() {
         return None;
     }
 
@@ -70,7 +121,7 @@ pub fn convert_sockaddr(sa: *mut socket::sockaddr) -> Option<net::SocketAddr> {
             let sa: *const socket::sockaddr_in = unsafe { mem::transmute(sa) };
             let sa = &unsafe { *sa };
             let (addr, port) = (sa.sin_addr.s_addr, sa.sin_port);
-            (net::IpAddr::V4(net::Ipv4Addr::new(((addr & 0x000000FF) >> 0) as u8,
+            (net::IpAddr::V4(net::Ipv4Addr::new((addr & 0x000000FF) as u8,
                                                 ((addr & 0x0000FF00) >> 8) as u8,
                                                 ((addr & 0x00FF0000) >> 16) as u8,
                                                 ((addr & 0xFF000000) >> 24) as u8)),
@@ -115,4 +166,17 @@ mod tests {
     fn test_make_int16() {
         assert_eq!(make_int16(0xff, 0x00), 0xff00);
     }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sockaddr_ll_max_layout() {
+        use std::mem;
+        use libc;
+        use super::{sockaddr_ll_max, SOCKADDR_LL_MAX_ADDR};
+
+        // Only the address differs from `sockaddr_ll`, which has room for eight bytes.
+        assert_eq!(mem::size_of::<sockaddr_ll_max>(),
+                   mem::size_of::<libc::sockaddr_ll>() - 8 + SOCKADDR_LL_MAX_ADDR);
+        assert_eq!(mem::align_of::<sockaddr_ll_max>(), mem::align_of::<libc::sockaddr_ll>());
+    }
 }
@@ -12,29 +12,80 @@ extern crate libc;
 extern crate nix;
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::ffi::CStr;
+#[cfg(not(target_os = "linux"))]
+use std::ffi::CString;
 use std::fmt;
+use std::fs::File;
+use std::io::Read;
 use std::mem;
 use std::net;
 use std::ptr;
+#[cfg(target_os = "linux")]
+use std::slice;
+use std::str::FromStr;
 
-use libc::c_int;
+use libc::{c_char, c_int};
 use libc::{close, ioctl, socket};
 use libc::{AF_INET, SOCK_DGRAM};
 
-#[cfg(target_os = "linux")]
-use nix::sys::socket;
-
-pub use error::InterfacesError;
+pub use error::{HardwareAddrError, InterfacesError};
 pub use flags::InterfaceFlags;
+pub use network::IpNetwork;
 
 mod constants;
 mod error;
 mod ffi;
+#[cfg(target_os = "linux")]
+mod netlink;
+#[cfg(target_os = "linux")]
+mod sysctl;
+#[cfg(all(test, target_os = "linux"))]
+mod testutil;
+
+/// Submodule for the flags, scope, lifetimes and labels of interface addresses (Linux only).
+#[cfg(target_os = "linux")]
+pub mod address;
+
+/// Submodule for reading and changing per-interface IPv4 and IPv6 settings (Linux only).
+#[cfg(target_os = "linux")]
+pub mod conf;
+
+/// Submodule for creating and inspecting virtual links (Linux only).
+#[cfg(target_os = "linux")]
+pub mod link;
+
+/// Submodule for reading and changing the ARP and NDP neighbor tables (Linux only).
+#[cfg(target_os = "linux")]
+pub mod neighbor;
+
+/// Submodule for working with network namespaces (Linux only).
+#[cfg(target_os = "linux")]
+pub mod netns;
+
+/// Submodule for reading and changing routing tables (Linux only).
+#[cfg(target_os = "linux")]
+pub mod route;
+
+/// Submodule for managing policy routing rules (Linux only).
+#[cfg(target_os = "linux")]
+pub mod rule;
+
+/// Submodule for creating TUN and TAP devices (Linux only).
+#[cfg(target_os = "linux")]
+pub mod tun;
+
+/// Submodule for configuring WireGuard devices (Linux only).
+#[cfg(target_os = "linux")]
+pub mod wireguard;
 
 /// Submodule containing various flags.
 pub mod flags;
 
+/// Submodule for IP networks and their host addresses.
+pub mod network;
+
 /// A specialized Result type for this crate.
 pub type Result<T> = ::std::result::Result<T, InterfacesError>;
 
@@ -81,6 +132,15 @@ pub enum NextHop {
     Destination(net::SocketAddr),
 }
 
+impl NextHop {
+    /// Returns the IP address of the broadcast or destination address.
+    pub fn ip(&self) -> net::IpAddr {
+        match *self {
+            NextHop::Broadcast(ref addr) | NextHop::Destination(ref addr) => addr.ip(),
+        }
+    }
+}
+
 impl fmt::Display for NextHop {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -97,13 +157,65 @@ pub struct Address {
     pub kind: Kind,
 
     /// The underlying socket address, if it applies.
+    #[deprecated(note = "the port is always zero; use `ip` and `scope_id` instead")]
     pub addr: Option<net::SocketAddr>,
 
     /// The netmask of this interface address, if it applies.
+    #[deprecated(note = "the port is always zero; use `netmask` instead")]
     pub mask: Option<net::SocketAddr>,
 
+    /// The IP address, if this is an IPv4 or IPv6 address.
+    pub ip: Option<net::IpAddr>,
+
+    /// The netmask of this interface address, if it applies.  Use `Address::prefix_len` or
+    /// `Address::network` to get it as a prefix length.
+    pub netmask: Option<net::IpAddr>,
+
+    /// The scope id of an IPv6 address, i.e. the index of the interface that a link-local
+    /// address belongs to.  This is zero for IPv4 and global IPv6 addresses.
+    pub scope_id: u32,
+
     /// The broadcast address or destination address, if it applies.
+    #[deprecated(note = "the port is always zero; use `broadcast` and `destination` instead")]
     pub hop: Option<NextHop>,
+
+    /// The broadcast address associated with this address, if the interface supports broadcast.
+    pub broadcast: Option<net::IpAddr>,
+
+    /// The address of the other end, if this is an address of a point-to-point interface.
+    pub destination: Option<net::IpAddr>,
+
+    /// Platform-specific information about this address.
+    pub metadata: AddressMetadata,
+}
+
+/// Platform-specific information about an address that `getifaddrs` does not report in a portable
+/// way.  Fields may be added in future versions, so this can only be created by this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct AddressMetadata {
+    /// Linux only: the link-layer address, for addresses of kind `Kind::Packet`.
+    #[cfg(target_os = "linux")]
+    pub link: Option<address::LinkAddress>,
+
+    /// Linux only: the flags of this address, e.g. whether it is still tentative.  See
+    /// `Address::is_usable`.
+    #[cfg(target_os = "linux")]
+    pub flags: address::AddressFlags,
+
+    /// Linux only: the scope of this address, e.g. `Link` for link-local addresses.
+    #[cfg(target_os = "linux")]
+    pub scope: Option<route::RouteScope>,
+
+    /// Linux only: the remaining preferred and valid lifetimes of this address.
+    #[cfg(target_os = "linux")]
+    pub lifetimes: Option<address::AddressLifetimes>,
+
+    // The label of an IPv4 address, padded with zeros; see `Address::label`.
+    #[cfg(target_os = "linux")]
+    label: [u8; ffi::IFNAMSIZ],
+    #[cfg(target_os = "linux")]
+    label_len: u8,
 }
 
 /// HardwareAddr represents a hardware address (commonly known as a MAC address) of a given
@@ -127,7 +239,7 @@ impl HardwareAddr {
     /// assert_eq!(s, "00:00:00:00:00:00");
     /// ```
     pub fn as_string(&self) -> String {
-        let &HardwareAddr(ref arr) = self;
+        let HardwareAddr(arr) = self;
 
         format!(
             "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
@@ -144,7 +256,7 @@ impl HardwareAddr {
     /// assert_eq!(s, "000000000000");
     /// ```
     pub fn as_bare_string(&self) -> String {
-        let &HardwareAddr(ref arr) = self;
+        let HardwareAddr(arr) = self;
 
         format!(
             "{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
@@ -152,6 +264,151 @@ impl HardwareAddr {
         )
     }
 
+    /// Returns a random, locally administered unicast address, e.g. for a new virtual interface.
+    /// The randomness is read from `/dev/urandom`.
+    pub fn random() -> Result<HardwareAddr> {
+        Ok(HardwareAddr::generate(None, random_bytes()?))
+    }
+
+    /// Returns an address with the given OUI (the first three bytes) and random remaining bytes.
+    /// The OUI must be locally administered and unicast, i.e. the first byte must have its
+    /// second lowest bit set and its lowest bit cleared (e.g. `02:1b:21`, not `00:1b:21`);
+    /// otherwise, this returns `EINVAL`.
+    pub fn random_with_oui(oui: [u8; 3]) -> Result<HardwareAddr> {
+        check_oui(oui).map_err(|_| InterfacesError::Errno(nix::errno::Errno::EINVAL))?;
+        Ok(HardwareAddr::generate(Some(oui), random_bytes()?))
+    }
+
+    /// Returns a locally administered unicast address derived from `seed`, e.g. the name of a
+    /// container.  The same seed always results in the same address, across runs and versions of
+    /// this crate.
+    ///
+    /// ```
+    /// # use interfaces::HardwareAddr;
+    /// let a = HardwareAddr::from_seed(b"container-1");
+    /// assert_eq!(a, HardwareAddr::from_seed(b"container-1"));
+    /// assert!(a.is_locally_administered() && a.is_unicast());
+    /// ```
+    pub fn from_seed(seed: &[u8]) -> HardwareAddr {
+        HardwareAddr::generate(None, seeded_bytes(seed))
+    }
+
+    /// Like `from_seed`, but with the given OUI.  Returns `HardwareAddrError::InvalidOui` if the
+    /// OUI is not locally administered and unicast; see `random_with_oui`.
+    pub fn from_seed_with_oui(
+        oui: [u8; 3],
+        seed: &[u8],
+    ) -> ::std::result::Result<HardwareAddr, HardwareAddrError> {
+        check_oui(oui)?;
+        Ok(HardwareAddr::generate(Some(oui), seeded_bytes(seed)))
+    }
+
+    fn generate(oui: Option<[u8; 3]>, mut bytes: [u8; 6]) -> HardwareAddr {
+        match oui {
+            Some(oui) => bytes[..3].copy_from_slice(&oui),
+            None => bytes[0] = (bytes[0] | 0x02) & !0x01,
+        }
+        HardwareAddr(bytes)
+    }
+
+    /// Returns whether this is a multicast address, i.e. the least significant bit of the first
+    /// byte is set.  This includes the broadcast address.
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    /// Returns whether this is the broadcast address `ff:ff:ff:ff:ff:ff`.
+    pub fn is_broadcast(&self) -> bool {
+        self.0 == [0xff; 6]
+    }
+
+    /// Returns whether this is a unicast address, i.e. not a multicast address.
+    pub fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    /// Returns whether this address is locally administered, i.e. the second least significant
+    /// bit of the first byte is set.  Such addresses are not assigned by a manufacturer.
+    pub fn is_locally_administered(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
+
+    /// Returns whether this address is universally administered, i.e. it was assigned by the
+    /// owner of its OUI.
+    pub fn is_universal(&self) -> bool {
+        !self.is_locally_administered()
+    }
+
+    /// Returns the organizationally unique identifier, i.e. the first three bytes.
+    ///
+    /// ```
+    /// # use interfaces::HardwareAddr;
+    /// let a: HardwareAddr = "52:54:00:12:34:56".parse().unwrap();
+    /// assert_eq!(a.oui(), [0x52, 0x54, 0x00]);
+    /// ```
+    pub fn oui(&self) -> [u8; 3] {
+        [self.0[0], self.0[1], self.0[2]]
+    }
+
+    /// Returns the modified EUI-64 interface identifier of this address, as used by IPv6 (RFC 4291
+    /// appendix A): `ff:fe` is inserted in the middle, and the universal/local bit is inverted.
+    ///
+    /// ```
+    /// # use interfaces::HardwareAddr;
+    /// let a: HardwareAddr = "00:1b:21:3a:4f:5e".parse().unwrap();
+    /// assert_eq!(a.to_eui64(), [0x02, 0x1b, 0x21, 0xff, 0xfe, 0x3a, 0x4f, 0x5e]);
+    /// ```
+    pub fn to_eui64(&self) -> [u8; 8] {
+        let b = self.0;
+        [b[0] ^ 0x02, b[1], b[2], 0xff, 0xfe, b[3], b[4], b[5]]
+    }
+
+    /// Returns the hardware address that a modified EUI-64 interface identifier was derived from,
+    /// or `None` if it was not derived from a hardware address.
+    pub fn from_eui64(id: [u8; 8]) -> Option<HardwareAddr> {
+        if id[3] != 0xff || id[4] != 0xfe {
+            return None;
+        }
+
+        Some(HardwareAddr([
+            id[0] ^ 0x02,
+            id[1],
+            id[2],
+            id[5],
+            id[6],
+            id[7],
+        ]))
+    }
+
+    /// Returns the IPv6 link-local address (`fe80::/64`) that the kernel generates for this address
+    /// when the interface uses EUI-64 address generation (see `conf::AddrGenMode`).
+    ///
+    /// ```
+    /// # use interfaces::HardwareAddr;
+    /// let a: HardwareAddr = "00:1b:21:3a:4f:5e".parse().unwrap();
+    /// assert_eq!(a.to_ipv6_link_local().to_string(), "fe80::21b:21ff:fe3a:4f5e");
+    /// ```
+    pub fn to_ipv6_link_local(&self) -> net::Ipv6Addr {
+        self.ipv6_slaac_address(net::Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0))
+    }
+
+    /// Returns the address that stateless address autoconfiguration (SLAAC) derives from this
+    /// address for the given prefix.  Only the first 64 bits of `prefix` are used, since SLAAC
+    /// always uses a `/64`.
+    pub fn ipv6_slaac_address(&self, prefix: net::Ipv6Addr) -> net::Ipv6Addr {
+        let mut bytes = prefix.octets();
+        bytes[8..].copy_from_slice(&self.to_eui64());
+        net::Ipv6Addr::from(bytes)
+    }
+
+    /// Returns the hardware address that an IPv6 address was derived from with EUI-64 (e.g. a
+    /// link-local or SLAAC address), or `None` if its interface identifier is not EUI-64 based.
+    pub fn from_ipv6(ip: net::Ipv6Addr) -> Option<HardwareAddr> {
+        let mut id = [0; 8];
+        id.copy_from_slice(&ip.octets()[8..]);
+        HardwareAddr::from_eui64(id)
+    }
+
     /// Returns the raw bytes representing this hardware address.
     ///
     /// ```
@@ -160,17 +417,121 @@ impl HardwareAddr {
     /// assert_eq!(s.as_bytes(), &[0, 0, 0, 0, 0, 0]);
     /// ```
     pub fn as_bytes(&self) -> &[u8] {
-        let &HardwareAddr(ref arr) = self;
+        let HardwareAddr(arr) = self;
         arr
     }
 }
 
+impl From<[u8; 6]> for HardwareAddr {
+    fn from(bytes: [u8; 6]) -> HardwareAddr {
+        HardwareAddr(bytes)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for HardwareAddr {
+    type Error = HardwareAddrError;
+
+    fn try_from(bytes: &'a [u8]) -> ::std::result::Result<HardwareAddr, HardwareAddrError> {
+        if bytes.len() != 6 {
+            return Err(HardwareAddrError::InvalidLength(bytes.len()));
+        }
+
+        let mut arr = [0; 6];
+        arr.copy_from_slice(bytes);
+        Ok(HardwareAddr(arr))
+    }
+}
+
+impl FromStr for HardwareAddr {
+    type Err = HardwareAddrError;
+
+    /// Parses a hardware address in any of the common formats: separated by colons
+    /// (`00:11:22:33:44:55`) or hyphens (`00-11-22-33-44-55`), Cisco-style groups of four digits
+    /// (`0011.2233.4455`), or bare (`001122334455`).  Digits may be in either case.
+    ///
+    /// ```
+    /// # use interfaces::HardwareAddr;
+    /// let a: HardwareAddr = "00-11-22-AA-BB-CC".parse().unwrap();
+    /// let b: HardwareAddr = "0011.22aa.bbcc".parse().unwrap();
+    /// assert_eq!(a, b);
+    /// assert_eq!(a.as_string(), "00:11:22:aa:bb:cc");
+    /// ```
+    fn from_str(s: &str) -> ::std::result::Result<HardwareAddr, HardwareAddrError> {
+        if let Some(c) = s
+            .chars()
+            .find(|c| !c.is_ascii_hexdigit() && !":-.".contains(*c))
+        {
+            return Err(HardwareAddrError::InvalidCharacter(c));
+        }
+
+        // Each format has a single kind of separator between groups of a fixed size.
+        let (sep, group_len) = match (s.contains(':'), s.contains('-'), s.contains('.')) {
+            (true, false, false) => (Some(':'), 2),
+            (false, true, false) => (Some('-'), 2),
+            (false, false, true) => (Some('.'), 4),
+            (false, false, false) => (None, 12),
+            _ => return Err(HardwareAddrError::InvalidFormat),
+        };
+
+        let groups: Vec<&str> = match sep {
+            Some(sep) => s.split(sep).collect(),
+            None => vec![s],
+        };
+        if groups.len() != 12 / group_len || groups.iter().any(|g| g.len() != group_len) {
+            return Err(HardwareAddrError::InvalidFormat);
+        }
+
+        let digits = groups.concat();
+        let mut arr = [0; 6];
+        for (i, b) in arr.iter_mut().enumerate() {
+            // Only ASCII hex digits are left, so this cannot fail.
+            *b = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        Ok(HardwareAddr(arr))
+    }
+}
+
 impl fmt::Display for HardwareAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.as_string())
     }
 }
 
+/// Reads six random bytes for `HardwareAddr::random`.
+fn random_bytes() -> Result<[u8; 6]> {
+    let mut bytes = [0; 6];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Checks that `oui` is locally administered and unicast, so that the addresses generated from it
+/// are too.
+fn check_oui(oui: [u8; 3]) -> ::std::result::Result<(), HardwareAddrError> {
+    if oui[0] & 0x03 != 0x02 {
+        return Err(HardwareAddrError::InvalidOui(oui));
+    }
+    Ok(())
+}
+
+/// Derives six bytes from a seed for `HardwareAddr::from_seed`.  This hashes the seed with 64-bit
+/// FNV-1a, and mixes the result with the SplitMix64 finalizer so that similar seeds still result
+/// in very different addresses.  Both are fixed algorithms, so the result never changes.
+fn seeded_bytes(seed: &[u8]) -> [u8; 6] {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in seed {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^= h >> 31;
+
+    let mut bytes = [0; 6];
+    bytes.copy_from_slice(&h.to_be_bytes()[..6]);
+    bytes
+}
+
 /// An iterator to walk through all `ifaddrs`.
 struct IfAddrIterator {
     orig: *mut ffi::ifaddrs,
@@ -185,10 +546,7 @@ impl IfAddrIterator {
             return Err(InterfacesError::last_os_error());
         }
 
-        Ok(IfAddrIterator {
-            orig: ifap,
-            ifap: ifap,
-        })
+        Ok(IfAddrIterator { orig: ifap, ifap })
     }
 }
 
@@ -236,16 +594,36 @@ pub struct Interface {
 
     // Information socket
     sock: c_int,
+
+    // The namespace this interface was found in, if `Netns::run` was used to find it.
+    #[cfg(target_os = "linux")]
+    netns: Option<netns::Netns>,
+
+    // The link-level information read during enumeration; see `Interface::link`.
+    #[cfg(target_os = "linux")]
+    link: Option<link::LinkInfo>,
 }
 
 impl Interface {
-    /// Retrieve a list of all interfaces on this system.
+    /// Retrieve a list of all interfaces on this system.  The addresses of IPv4 aliases (e.g.
+    /// `eth0:1`) belong to the interface they are configured on; see `Address::label`.
     pub fn get_all() -> Result<Vec<Interface>> {
+        Interface::get_all_impl(true)
+    }
+
+    /// Like `get_all`, but returns each IPv4 alias as a separate interface with only the
+    /// addresses of that alias, like `ifconfig` does.
+    pub fn get_all_with_aliases() -> Result<Vec<Interface>> {
+        Interface::get_all_impl(false)
+    }
+
+    fn get_all_impl(fold_aliases: bool) -> Result<Vec<Interface>> {
         // Map each interface address to a single interface name.
         let mut ifs = HashMap::new();
         for cur in IfAddrIterator::new()? {
             // Only support interfaces with valid names.
             let ifname = match convert_ifaddr_name(cur) {
+                Some(n) if fold_aliases => base_name(&n).to_string(),
                 Some(n) => n,
                 None => continue,
             };
@@ -253,7 +631,7 @@ impl Interface {
             let iface = if ifs.contains_key(&ifname) {
                 ifs.get_mut(&ifname).unwrap()
             } else {
-                let new_if = match Interface::new_from_ptr(cur) {
+                let new_if = match Interface::new_from_ptr(cur, ifname.clone()) {
                     Ok(i) => i,
                     Err(_) => continue,
                 };
@@ -267,7 +645,13 @@ impl Interface {
             }
         }
 
-        let ret = ifs.into_iter().map(|(_, v)| v).collect::<Vec<_>>();
+        #[allow(unused_mut)]
+        let mut ret = ifs.into_values().collect::<Vec<_>>();
+        #[cfg(target_os = "linux")]
+        {
+            link::fill_link_info(&mut ret)?;
+            address::fill_metadata(&mut ret)?;
+        }
         Ok(ret)
     }
 
@@ -287,6 +671,9 @@ impl Interface {
     /// # Ok(iface)
     /// # }
     /// ```
+    ///
+    /// The name of an IPv4 alias (e.g. `eth0:1`) returns only the addresses of that alias, as in
+    /// `get_all_with_aliases`.
     pub fn get_by_name(name: &str) -> Result<Option<Interface>> {
         let mut ret = None;
 
@@ -297,14 +684,14 @@ impl Interface {
                 None => continue,
             };
 
-            if ifname != name {
+            if ifname != name && base_name(&ifname) != name {
                 continue;
             }
 
             // Get or create the Interface
             let mut i = match ret.take() {
                 Some(i) => i,
-                None => Interface::new_from_ptr(cur)?,
+                None => Interface::new_from_ptr(cur, name.to_string())?,
             };
 
             // If we can, convert this current address.
@@ -315,17 +702,40 @@ impl Interface {
             ret = Some(i);
         }
 
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(ref mut i) = ret {
+                link::fill_link_info(slice::from_mut(i))?;
+                address::fill_metadata(slice::from_mut(i))?;
+            }
+        }
+
         Ok(ret)
     }
 
-    /// Create a new Interface from a given `ffi::ifaddrs`.
-    fn new_from_ptr(ifa: *mut ffi::ifaddrs) -> Result<Interface> {
-        let ifa = unsafe { &mut *ifa };
+    /// Returns an `Interface` instance representing the interface with the given index.  Like
+    /// `get_by_name`, this returns `Ok(None)` if there is no such interface.
+    pub fn get_by_index(index: u32) -> Result<Option<Interface>> {
+        let mut buf = [0 as c_char; ffi::IFNAMSIZ];
+        let res = unsafe { libc::if_indextoname(index, buf.as_mut_ptr()) };
+        if res.is_null() {
+            return match nix::errno::Errno::last() {
+                nix::errno::Errno::ENXIO | nix::errno::Errno::ENODEV => Ok(None),
+                err => Err(InterfacesError::Errno(err)),
+            };
+        }
+
+        let name = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        match name.to_str() {
+            Ok(name) => Interface::get_by_name(name),
+            Err(_) => Ok(None),
+        }
+    }
 
-        // NOTE: can unwrap() here since we only call this function if the prior call to
-        // convert_ifaddr_name succeeded.  It's a bit sad that we have to duplicate work, but not a
-        // huge deal.
-        let name = convert_ifaddr_name(ifa).unwrap();
+    /// Create a new Interface with the given name from a given `ffi::ifaddrs`.  The name is
+    /// passed in, since the `ifaddrs` may belong to an alias of the interface.
+    fn new_from_ptr(ifa: *mut ffi::ifaddrs, name: String) -> Result<Interface> {
+        let ifa = unsafe { &mut *ifa };
 
         // Try to create a socket that we use to get info about this interface.
         let sock = unsafe { socket(AF_INET, SOCK_DGRAM, 0) };
@@ -335,10 +745,14 @@ impl Interface {
 
         let flags = InterfaceFlags::from_bits_truncate(ifa.ifa_flags);
         Ok(Interface {
-            name: name,
+            name,
             addresses: vec![],
-            flags: flags,
-            sock: sock,
+            flags,
+            sock,
+            #[cfg(target_os = "linux")]
+            netns: netns::Netns::thread_current(),
+            #[cfg(target_os = "linux")]
+            link: None,
         })
     }
 
@@ -357,43 +771,17 @@ impl Interface {
         self.flags.contains(InterfaceFlags::IFF_LOOPBACK)
     }
 
-    /// Retrieves the hardware address of this interface.
+    /// Retrieves the hardware address of this interface.  On Linux, this returns `NotSupported`
+    /// for links whose address is not six bytes long (e.g. InfiniBand or TUN devices); use
+    /// `Interface::link_address` for those.
     pub fn hardware_addr(&self) -> Result<HardwareAddr> {
         self.hardware_addr_impl()
     }
 
     #[cfg(target_os = "linux")]
-    #[allow(non_snake_case)]
     fn hardware_addr_impl(&self) -> Result<HardwareAddr> {
-        // We need this IOCTL in order to get the hardware address.
-        let SIOCGIFHWADDR = match constants::get_constant("SIOCGIFHWADDR") {
-            Some(c) => c,
-            None => return Err(InterfacesError::NotSupported("SIOCGIFHWADDR")),
-        };
-
-        let mut req = ffi::ifreq_with_hwaddr {
-            ifr_name: [0; ffi::IFNAMSIZ],
-            ifr_hwaddr: socket::sockaddr {
-                sa_family: 0,
-                sa_data: [0; 14],
-            },
-        };
-
-        copy_slice(&mut req.ifr_name, self.name.as_bytes());
-
-        let res = unsafe { ioctl(self.sock, SIOCGIFHWADDR, &mut req) };
-        if res < 0 {
-            return Err(InterfacesError::last_os_error());
-        }
-
-        let mut addr = [0; 6];
-        for i in 0..6 {
-            addr[i] = req.ifr_hwaddr.sa_data[i];
-        }
-
-        // Hardware addresses are `i8`s on some Linux versions for some reason?
-        let addr = unsafe { mem::transmute::<[_; 6], [u8; 6]>(addr) };
-        Ok(HardwareAddr(addr))
+        HardwareAddr::try_from(&self.link_address()?)
+            .map_err(|_| InterfacesError::NotSupported("Not a 6-byte hardware address"))
     }
 
     #[cfg(target_os = "macos")]
@@ -497,6 +885,52 @@ impl Interface {
         Ok(())
     }
 
+    /// Retrieve the index of this interface, as used by the kernel to identify it.
+    pub fn index(&self) -> Result<u32> {
+        self.index_impl()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[allow(non_snake_case)]
+    fn index_impl(&self) -> Result<u32> {
+        if let Some(ref link) = self.link {
+            return Ok(link.index);
+        }
+
+        let SIOCGIFINDEX = match constants::get_constant("SIOCGIFINDEX") {
+            Some(c) => c,
+            None => return Err(InterfacesError::NotSupported("SIOCGIFINDEX")),
+        };
+
+        let mut req = ffi::ifreq_with_ifindex {
+            ifr_name: [0; ffi::IFNAMSIZ],
+            ifr_ifindex: 0,
+            ifr_pad: [0; 20],
+        };
+
+        copy_slice(&mut req.ifr_name, self.name.as_bytes());
+
+        let res = unsafe { ioctl(self.sock, SIOCGIFINDEX, &mut req) };
+        if res < 0 {
+            return Err(InterfacesError::last_os_error());
+        }
+
+        Ok(req.ifr_ifindex as u32)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn index_impl(&self) -> Result<u32> {
+        let name = match CString::new(self.name.as_bytes()) {
+            Ok(n) => n,
+            Err(_) => return Err(InterfacesError::NotSupported("Invalid interface name")),
+        };
+
+        match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+            0 => Err(InterfacesError::last_os_error()),
+            index => Ok(index),
+        }
+    }
+
     /// Retrieve the MTU of this interface.
     #[allow(non_snake_case)]
     pub fn get_mtu(&self) -> Result<u32> {
@@ -529,6 +963,15 @@ fn convert_ifaddr_name(ifa: *mut ffi::ifaddrs) -> Option<String> {
     }
 }
 
+/// Returns the name of the interface that an IPv4 alias like `eth0:1` belongs to.  Interface names
+/// cannot contain colons, so other names are returned as they are.
+fn base_name(name: &str) -> &str {
+    match name.find(':') {
+        Some(i) => &name[..i],
+        None => name,
+    }
+}
+
 // This is a bit scary, but the various address families are different from platform to platform,
 // and also from OS version to OS version.  Essentially, we have a couple of families that we know
 // about (IPv4, IPv6, etc.), and a couple that we determined at build time by compiling some C code
@@ -559,7 +1002,7 @@ fn convert_ifaddr_family(family: i32) -> Kind {
 fn convert_ifaddr_address(ifa: *mut ffi::ifaddrs) -> Option<Address> {
     let ifa = unsafe { &mut *ifa };
 
-    let kind = if ifa.ifa_addr != ptr::null_mut() {
+    let kind = if !ifa.ifa_addr.is_null() {
         let fam = unsafe { *ifa.ifa_addr }.sa_family as i32;
         convert_ifaddr_family(fam)
     } else {
@@ -572,25 +1015,66 @@ fn convert_ifaddr_address(ifa: *mut ffi::ifaddrs) -> Option<Address> {
 
     let flags = InterfaceFlags::from_bits_truncate(ifa.ifa_flags);
     let hop = if flags.contains(InterfaceFlags::IFF_BROADCAST) {
-        match ffi::convert_sockaddr(ifa.ifa_ifu.ifu_broadaddr()) {
-            Some(x) => Some(NextHop::Broadcast(x)),
-            None => None,
-        }
+        ffi::convert_sockaddr(ifa.ifa_ifu.ifu_broadaddr()).map(NextHop::Broadcast)
     } else {
-        match ffi::convert_sockaddr(ifa.ifa_ifu.ifu_dstaddr()) {
-            Some(x) => Some(NextHop::Destination(x)),
-            None => None,
+        ffi::convert_sockaddr(ifa.ifa_ifu.ifu_dstaddr()).map(NextHop::Destination)
+    };
+
+    // macOS embeds the scope of link-local addresses in the address itself (the KAME hack).
+    let (ip, scope_id) = match addr {
+        Some(net::SocketAddr::V6(ref a)) if cfg!(target_os = "macos") => {
+            let (ip, scope_id) = unembed_scope_id(*a.ip(), a.scope_id());
+            (Some(net::IpAddr::V6(ip)), scope_id)
         }
+        Some(net::SocketAddr::V6(ref a)) => (Some(net::IpAddr::V6(*a.ip())), a.scope_id()),
+        Some(a) => (Some(a.ip()), 0),
+        None => (None, 0),
     };
 
+    #[allow(deprecated)]
     Some(Address {
-        kind: kind,
-        addr: addr,
-        mask: mask,
-        hop: hop,
+        kind,
+        addr,
+        mask,
+        ip,
+        netmask: mask.map(|a| a.ip()),
+        scope_id,
+        hop,
+        broadcast: match hop {
+            Some(NextHop::Broadcast(addr)) => Some(addr.ip()),
+            _ => None,
+        },
+        destination: match hop {
+            Some(NextHop::Destination(addr)) => Some(addr.ip()),
+            _ => None,
+        },
+        metadata: AddressMetadata {
+            #[cfg(target_os = "linux")]
+            link: ffi::convert_sockaddr_ll(ifa.ifa_addr),
+            ..AddressMetadata::default()
+        },
     })
 }
 
+/// Moves a scope id that is embedded in the second group of a link-local address into the scope
+/// id, as BSD kernels report them.
+fn unembed_scope_id(ip: net::Ipv6Addr, scope_id: u32) -> (net::Ipv6Addr, u32) {
+    let mut segments = ip.segments();
+    if !is_link_local(&ip) || segments[1] == 0 {
+        return (ip, scope_id);
+    }
+
+    let embedded = segments[1] as u32;
+    segments[1] = 0;
+    let scope_id = if scope_id == 0 { embedded } else { scope_id };
+    (net::Ipv6Addr::from(segments), scope_id)
+}
+
+/// Returns whether `ip` is in `fe80::/10`.
+fn is_link_local(ip: &net::Ipv6Addr) -> bool {
+    ip.segments()[0] & 0xffc0 == 0xfe80
+}
+
 impl PartialEq for Interface {
     fn eq(&self, other: &Interface) -> bool {
         self.name == other.name
@@ -649,6 +1133,171 @@ mod tests {
         assert_eq!(h.as_bare_string(), "000000000000");
     }
 
+    #[test]
+    fn test_address_is_copy() {
+        fn assert_copy<T: Copy>() {}
+        assert_copy::<Address>();
+        assert_copy::<AddressMetadata>();
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_address_ip() {
+        for i in Interface::get_all().unwrap() {
+            for addr in i.addresses.iter() {
+                if !cfg!(target_os = "macos") {
+                    assert_eq!(addr.ip, addr.addr.map(|a| a.ip()));
+                }
+                assert_eq!(addr.netmask, addr.mask.map(|a| a.ip()));
+                match addr.hop {
+                    Some(NextHop::Broadcast(a)) => assert_eq!(addr.broadcast, Some(a.ip())),
+                    Some(NextHop::Destination(a)) => assert_eq!(addr.destination, Some(a.ip())),
+                    None => assert_eq!((addr.broadcast, addr.destination), (None, None)),
+                }
+
+                match addr.ip {
+                    Some(net::IpAddr::V6(ref ip)) if is_link_local(ip) => {
+                        assert_eq!(addr.scope_id, i.index().unwrap());
+                    }
+                    _ => assert_eq!(addr.scope_id, 0),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unembed_scope_id() {
+        let ip: net::Ipv6Addr = "fe80:4::1".parse().unwrap();
+        assert_eq!(unembed_scope_id(ip, 0), ("fe80::1".parse().unwrap(), 4));
+        assert_eq!(unembed_scope_id(ip, 7), ("fe80::1".parse().unwrap(), 7));
+
+        let ip: net::Ipv6Addr = "febf:4::1".parse().unwrap();
+        assert_eq!(unembed_scope_id(ip, 0), ("febf::1".parse().unwrap(), 4));
+
+        let ip: net::Ipv6Addr = "fd00:4::1".parse().unwrap();
+        assert_eq!(unembed_scope_id(ip, 0), (ip, 0));
+        let ip: net::Ipv6Addr = "fe80::1".parse().unwrap();
+        assert_eq!(unembed_scope_id(ip, 3), (ip, 3));
+    }
+
+    #[test]
+    fn test_hardwareaddr_parse() {
+        let expected = HardwareAddr::from([0x00, 0x11, 0x22, 0xaa, 0xbb, 0xcc]);
+        for s in &[
+            "00:11:22:aa:bb:cc",
+            "00-11-22-AA-BB-CC",
+            "0011.22aa.bbcc",
+            "001122AABBCC",
+        ] {
+            assert_eq!(s.parse::<HardwareAddr>(), Ok(expected));
+        }
+
+        let cases = [
+            ("00:11:22:aa:bb", HardwareAddrError::InvalidFormat),
+            ("0:11:22:aa:bb:cc", HardwareAddrError::InvalidFormat),
+            ("00:11-22:aa:bb:cc", HardwareAddrError::InvalidFormat),
+            ("0011.22aa.bbcc.dd", HardwareAddrError::InvalidFormat),
+            ("00112233445", HardwareAddrError::InvalidFormat),
+            ("", HardwareAddrError::InvalidFormat),
+            (
+                "00:11:22:aa:bb:cg",
+                HardwareAddrError::InvalidCharacter('g'),
+            ),
+            (
+                " 00:11:22:aa:bb:cc",
+                HardwareAddrError::InvalidCharacter(' '),
+            ),
+        ];
+        for &(s, ref err) in cases.iter() {
+            assert_eq!(s.parse::<HardwareAddr>().as_ref(), Err(err), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_hardwareaddr_try_from() {
+        let bytes = [2, 0, 0, 0, 0, 1, 7];
+        assert_eq!(
+            HardwareAddr::try_from(&bytes[..6]).unwrap().as_bytes(),
+            &bytes[..6]
+        );
+        assert_eq!(
+            HardwareAddr::try_from(&bytes[..]),
+            Err(HardwareAddrError::InvalidLength(7))
+        );
+    }
+
+    #[test]
+    fn test_hardwareaddr_classification() {
+        let parse = |s: &str| s.parse::<HardwareAddr>().unwrap();
+
+        let bcast = parse("ff:ff:ff:ff:ff:ff");
+        assert!(bcast.is_broadcast() && bcast.is_multicast() && !bcast.is_unicast());
+
+        let mcast = parse("01:00:5e:00:00:01");
+        assert!(mcast.is_multicast() && !mcast.is_broadcast() && mcast.is_universal());
+
+        let local = parse("02:42:ac:11:00:02");
+        assert!(local.is_unicast() && local.is_locally_administered());
+        assert_eq!(local.oui(), [0x02, 0x42, 0xac]);
+
+        let universal = parse("00:1b:21:3a:4f:5e");
+        assert!(universal.is_unicast() && universal.is_universal());
+    }
+
+    #[test]
+    fn test_hardwareaddr_generate() {
+        for _ in 0..32 {
+            let a = HardwareAddr::random().unwrap();
+            assert!(a.is_unicast() && a.is_locally_administered());
+        }
+        let a = HardwareAddr::random_with_oui([0x52, 0x54, 0x00]).unwrap();
+        assert_eq!(a.oui(), [0x52, 0x54, 0x00]);
+
+        // Seeded addresses must never change, since they are used as stable addresses.
+        let a = HardwareAddr::from_seed(b"container-1");
+        assert_eq!(a.as_string(), "e2:13:3e:a4:bd:1a");
+        assert!(a.is_unicast() && a.is_locally_administered());
+        assert_ne!(a, HardwareAddr::from_seed(b"container-2"));
+        let a = HardwareAddr::from_seed_with_oui([0x52, 0x54, 0x00], b"container-1").unwrap();
+        assert_eq!(a.as_string(), "52:54:00:a4:bd:1a");
+
+        // Universal and multicast OUIs are rejected rather than changed.
+        for oui in &[[0x00, 0x1b, 0x21], [0x03, 0x00, 0x5e], [0x01, 0x00, 0x5e]] {
+            assert_eq!(
+                HardwareAddr::from_seed_with_oui(*oui, b"container-1"),
+                Err(HardwareAddrError::InvalidOui(*oui))
+            );
+            match HardwareAddr::random_with_oui(*oui) {
+                Err(InterfacesError::Errno(nix::errno::Errno::EINVAL)) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+    }
+
+    #[test]
+    fn test_hardwareaddr_eui64() {
+        let a: HardwareAddr = "02:42:ac:11:00:02".parse().unwrap();
+        assert_eq!(
+            a.to_eui64(),
+            [0x00, 0x42, 0xac, 0xff, 0xfe, 0x11, 0x00, 0x02]
+        );
+        assert_eq!(HardwareAddr::from_eui64(a.to_eui64()), Some(a));
+        assert_eq!(HardwareAddr::from_eui64([0; 8]), None);
+
+        let ll = a.to_ipv6_link_local();
+        assert_eq!(ll.to_string(), "fe80::42:acff:fe11:2");
+        assert_eq!(HardwareAddr::from_ipv6(ll), Some(a));
+
+        let prefix = "2001:db8:1:2:aaaa::".parse().unwrap();
+        let slaac = a.ipv6_slaac_address(prefix);
+        assert_eq!(slaac.to_string(), "2001:db8:1:2:42:acff:fe11:2");
+        assert_eq!(HardwareAddr::from_ipv6(slaac), Some(a));
+        assert_eq!(
+            HardwareAddr::from_ipv6("2001:db8::1".parse().unwrap()),
+            None
+        );
+    }
+
     fn assert_is_clone<T: Clone>(_: &T) {}
     fn assert_is_copy<T: Copy>(_: &T) {}
     fn assert_is_hash<T: Hash>(_: &T) {}
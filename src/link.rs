@@ -0,0 +1,1597 @@
+//! Creating and inspecting virtual links.  This is only available on Linux, where it is built on
+//! top of `rtnetlink`.
+
+use std::fmt;
+use std::fs;
+use std::net;
+use std::path::Path;
+
+use nix::errno::Errno;
+
+use address::LinkType;
+use netlink::{self, Message, Request, Socket};
+use netns::Netns;
+use {base_name, Address, Interface, InterfacesError, Kind, NextHop, Result};
+
+/// Where sysfs lists the network interfaces of the namespace it was mounted in.
+const SYSFS_NET: &str = "/sys/class/net";
+
+// Attributes inside `IFLA_INFO_DATA` for macvlan links.
+const IFLA_MACVLAN_MODE: u16 = 1;
+
+const MACVLAN_MODE_PRIVATE: u32 = 1;
+const MACVLAN_MODE_VEPA: u32 = 2;
+const MACVLAN_MODE_BRIDGE: u32 = 4;
+const MACVLAN_MODE_PASSTHRU: u32 = 8;
+const MACVLAN_MODE_SOURCE: u32 = 16;
+
+// Attributes inside `IFLA_INFO_DATA` for ipvlan links.
+const IFLA_IPVLAN_MODE: u16 = 1;
+
+const IPVLAN_MODE_L2: u16 = 0;
+const IPVLAN_MODE_L3: u16 = 1;
+const IPVLAN_MODE_L3S: u16 = 2;
+
+// Attributes inside `IFLA_INFO_DATA` for VXLAN links.
+const IFLA_VXLAN_ID: u16 = 1;
+const IFLA_VXLAN_GROUP: u16 = 2;
+const IFLA_VXLAN_LINK: u16 = 3;
+const IFLA_VXLAN_LOCAL: u16 = 4;
+const IFLA_VXLAN_TTL: u16 = 5;
+const IFLA_VXLAN_LEARNING: u16 = 7;
+const IFLA_VXLAN_PORT: u16 = 15;
+const IFLA_VXLAN_GROUP6: u16 = 16;
+const IFLA_VXLAN_LOCAL6: u16 = 17;
+
+// Attributes inside `IFLA_INFO_DATA` for Geneve links.
+const IFLA_GENEVE_ID: u16 = 1;
+const IFLA_GENEVE_REMOTE: u16 = 2;
+const IFLA_GENEVE_TTL: u16 = 3;
+const IFLA_GENEVE_PORT: u16 = 5;
+const IFLA_GENEVE_REMOTE6: u16 = 7;
+
+// Attributes inside `IFLA_INFO_DATA` for IPIP, SIT and ip6tnl links.
+const IFLA_IPTUN_LINK: u16 = 1;
+const IFLA_IPTUN_LOCAL: u16 = 2;
+const IFLA_IPTUN_REMOTE: u16 = 3;
+const IFLA_IPTUN_TTL: u16 = 4;
+const IFLA_IPTUN_ENCAP_LIMIT: u16 = 6;
+const IFLA_IPTUN_FLAGS: u16 = 8;
+
+// Attributes inside `IFLA_INFO_DATA` for GRE links, over both IPv4 and IPv6.
+const IFLA_GRE_LINK: u16 = 1;
+const IFLA_GRE_IFLAGS: u16 = 2;
+const IFLA_GRE_OFLAGS: u16 = 3;
+const IFLA_GRE_IKEY: u16 = 4;
+const IFLA_GRE_OKEY: u16 = 5;
+const IFLA_GRE_LOCAL: u16 = 6;
+const IFLA_GRE_REMOTE: u16 = 7;
+const IFLA_GRE_TTL: u16 = 8;
+const IFLA_GRE_ENCAP_LIMIT: u16 = 11;
+const IFLA_GRE_FLAGS: u16 = 13;
+
+// Attributes inside `IFLA_INFO_DATA` for VRF links.
+const IFLA_VRF_TABLE: u16 = 1;
+
+// Attributes inside `IFLA_INFO_DATA` for veth links.
+const VETH_INFO_PEER: u16 = 1;
+
+/// The id of the namespace that the interface in `IFLA_LINK` lives in, if it is not this one.
+const IFLA_LINK_NETNSID: u16 = 37;
+
+/// Set in the GRE flags when the header carries a key.
+const GRE_KEY: u16 = 0x2000;
+
+/// Set in the flags of IPv6 tunnels when no encapsulation limit option should be sent.
+const IP6_TNL_F_IGN_ENCAP_LIMIT: u32 = 0x1;
+
+/// The encapsulation limit that the kernel uses for IPv6 tunnels by default.
+const IPV6_DEFAULT_TNL_ENCAP_LIMIT: u8 = 4;
+
+/// VXLAN and Geneve network identifiers are 24 bits wide.
+const MAX_VNI: u32 = (1 << 24) - 1;
+
+/// The IANA-assigned UDP port for VXLAN.
+pub const VXLAN_PORT: u16 = 4789;
+
+/// The IANA-assigned UDP port for Geneve.
+pub const GENEVE_PORT: u16 = 6081;
+
+/// The mode of a macvlan link, which decides how it forwards traffic between itself and the
+/// other macvlan links on the same parent.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MacvlanMode {
+    /// Traffic is never forwarded to other macvlan links on the same parent.
+    Private,
+
+    /// Traffic to other macvlan links is sent out of the parent, and relies on an external switch
+    /// to hairpin it back.
+    Vepa,
+
+    /// Traffic to other macvlan links is bridged directly, without leaving the host.
+    Bridge,
+
+    /// The macvlan link takes over the parent entirely.  Only one such link may exist per parent.
+    Passthru,
+
+    /// Only traffic from an allowed list of source MAC addresses is accepted.
+    Source,
+}
+
+impl MacvlanMode {
+    fn to_raw(self) -> u32 {
+        match self {
+            MacvlanMode::Private => MACVLAN_MODE_PRIVATE,
+            MacvlanMode::Vepa => MACVLAN_MODE_VEPA,
+            MacvlanMode::Bridge => MACVLAN_MODE_BRIDGE,
+            MacvlanMode::Passthru => MACVLAN_MODE_PASSTHRU,
+            MacvlanMode::Source => MACVLAN_MODE_SOURCE,
+        }
+    }
+
+    fn from_raw(v: u32) -> Option<MacvlanMode> {
+        match v {
+            MACVLAN_MODE_PRIVATE => Some(MacvlanMode::Private),
+            MACVLAN_MODE_VEPA => Some(MacvlanMode::Vepa),
+            MACVLAN_MODE_BRIDGE => Some(MacvlanMode::Bridge),
+            MACVLAN_MODE_PASSTHRU => Some(MacvlanMode::Passthru),
+            MACVLAN_MODE_SOURCE => Some(MacvlanMode::Source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MacvlanMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MacvlanMode::Private => write!(f, "private"),
+            MacvlanMode::Vepa => write!(f, "vepa"),
+            MacvlanMode::Bridge => write!(f, "bridge"),
+            MacvlanMode::Passthru => write!(f, "passthru"),
+            MacvlanMode::Source => write!(f, "source"),
+        }
+    }
+}
+
+/// The mode of an ipvlan link, which decides at which layer packets are switched to it.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum IpvlanMode {
+    /// Packets are switched at layer 2; the link shares the MAC address of its parent.
+    L2,
+
+    /// Packets are routed at layer 3, without going through netfilter in the link's namespace.
+    L3,
+
+    /// Like `L3`, but packets pass through netfilter (and conntrack) in the link's namespace.
+    L3s,
+}
+
+impl IpvlanMode {
+    fn to_raw(self) -> u16 {
+        match self {
+            IpvlanMode::L2 => IPVLAN_MODE_L2,
+            IpvlanMode::L3 => IPVLAN_MODE_L3,
+            IpvlanMode::L3s => IPVLAN_MODE_L3S,
+        }
+    }
+
+    fn from_raw(v: u16) -> Option<IpvlanMode> {
+        match v {
+            IPVLAN_MODE_L2 => Some(IpvlanMode::L2),
+            IPVLAN_MODE_L3 => Some(IpvlanMode::L3),
+            IPVLAN_MODE_L3S => Some(IpvlanMode::L3s),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for IpvlanMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IpvlanMode::L2 => write!(f, "l2"),
+            IpvlanMode::L3 => write!(f, "l3"),
+            IpvlanMode::L3s => write!(f, "l3s"),
+        }
+    }
+}
+
+/// The settings of a VXLAN link.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct VxlanConfig {
+    /// The VXLAN network identifier.
+    pub vni: u32,
+
+    /// The unicast address of the remote tunnel endpoint, if there is a single one.
+    pub remote: Option<net::IpAddr>,
+
+    /// The multicast group used to reach all remote tunnel endpoints.  This is mutually exclusive
+    /// with `remote`.
+    pub group: Option<net::IpAddr>,
+
+    /// The local address to send packets from.
+    pub local: Option<net::IpAddr>,
+
+    /// The index of the interface to send packets through, e.g. for multicast.
+    pub device: Option<u32>,
+
+    /// The destination UDP port.
+    pub port: u16,
+
+    /// Whether unknown source addresses are learnt into the forwarding database.
+    pub learning: bool,
+
+    /// The TTL of outgoing packets.  `None` means that it is chosen by the kernel.
+    pub ttl: Option<u8>,
+}
+
+impl VxlanConfig {
+    /// Returns the default settings for a VXLAN link with the given network identifier: the IANA
+    /// port, with learning enabled, and no endpoints.
+    pub fn new(vni: u32) -> VxlanConfig {
+        VxlanConfig {
+            vni,
+            remote: None,
+            group: None,
+            local: None,
+            device: None,
+            port: VXLAN_PORT,
+            learning: true,
+            ttl: None,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.vni > MAX_VNI || (self.remote.is_some() && self.group.is_some()) {
+            return Err(InterfacesError::Errno(Errno::EINVAL));
+        }
+        if self.remote.is_some_and(|ip| ip.is_multicast())
+            || self.group.is_some_and(|ip| !ip.is_multicast())
+        {
+            return Err(InterfacesError::Errno(Errno::EINVAL));
+        }
+
+        // All addresses must be of the same family as the outer packets.
+        let mut ips = self
+            .local
+            .iter()
+            .chain(self.remote.iter())
+            .chain(self.group.iter());
+        if let Some(first) = ips.next() {
+            if ips.any(|ip| ip.is_ipv6() != first.is_ipv6()) {
+                return Err(InterfacesError::Errno(Errno::EINVAL));
+            }
+        }
+        Ok(())
+    }
+
+    fn put_data(&self, req: &mut Request) {
+        req.attr_u32(IFLA_VXLAN_ID, self.vni);
+
+        // The kernel keeps the remote and the group in the same attribute, and tells them apart
+        // by whether the address is multicast.
+        if let Some(ip) = self.group.or(self.remote) {
+            match ip {
+                net::IpAddr::V4(..) => req.attr_ip(IFLA_VXLAN_GROUP, ip),
+                net::IpAddr::V6(..) => req.attr_ip(IFLA_VXLAN_GROUP6, ip),
+            }
+        }
+        if let Some(ip) = self.local {
+            match ip {
+                net::IpAddr::V4(..) => req.attr_ip(IFLA_VXLAN_LOCAL, ip),
+                net::IpAddr::V6(..) => req.attr_ip(IFLA_VXLAN_LOCAL6, ip),
+            }
+        }
+        if let Some(device) = self.device {
+            req.attr_u32(IFLA_VXLAN_LINK, device);
+        }
+
+        req.attr(IFLA_VXLAN_PORT, &self.port.to_be_bytes());
+        req.attr_u8(IFLA_VXLAN_LEARNING, self.learning as u8);
+        req.attr_u8(IFLA_VXLAN_TTL, self.ttl.unwrap_or(0));
+    }
+
+    fn parse(data: &[u8]) -> Option<VxlanConfig> {
+        let mut ret = VxlanConfig::new(0);
+        let mut has_id = false;
+
+        for (ty, val) in netlink::Attrs::new(data) {
+            match ty {
+                IFLA_VXLAN_ID => {
+                    ret.vni = netlink::parse_u32(val)?;
+                    has_id = true;
+                }
+                IFLA_VXLAN_GROUP | IFLA_VXLAN_GROUP6 => {
+                    let ip = netlink::parse_ip(val).filter(|ip| !ip.is_unspecified());
+                    match ip {
+                        Some(ip) if ip.is_multicast() => ret.group = Some(ip),
+                        Some(ip) => ret.remote = Some(ip),
+                        None => {}
+                    }
+                }
+                IFLA_VXLAN_LOCAL | IFLA_VXLAN_LOCAL6 => {
+                    ret.local = netlink::parse_ip(val).filter(|ip| !ip.is_unspecified())
+                }
+                IFLA_VXLAN_LINK => ret.device = netlink::parse_u32(val).filter(|&i| i != 0),
+                IFLA_VXLAN_PORT => ret.port = netlink::parse_u16(val).map(u16::from_be)?,
+                IFLA_VXLAN_LEARNING => ret.learning = netlink::parse_u8(val)? != 0,
+                IFLA_VXLAN_TTL => ret.ttl = netlink::parse_u8(val).filter(|&ttl| ttl != 0),
+                _ => {}
+            }
+        }
+
+        if has_id {
+            Some(ret)
+        } else {
+            None
+        }
+    }
+}
+
+/// The settings of a Geneve link.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct GeneveConfig {
+    /// The virtual network identifier.
+    pub vni: u32,
+
+    /// The address of the remote tunnel endpoint.
+    pub remote: Option<net::IpAddr>,
+
+    /// The destination UDP port.
+    pub port: u16,
+
+    /// The TTL of outgoing packets.  `None` means that it is chosen by the kernel.
+    pub ttl: Option<u8>,
+}
+
+impl GeneveConfig {
+    /// Returns the default settings for a Geneve link with the given network identifier and
+    /// remote endpoint, using the IANA port.
+    pub fn new(vni: u32, remote: net::IpAddr) -> GeneveConfig {
+        GeneveConfig {
+            vni,
+            remote: Some(remote),
+            port: GENEVE_PORT,
+            ttl: None,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.vni > MAX_VNI || self.remote.is_some_and(|ip| ip.is_multicast()) {
+            return Err(InterfacesError::Errno(Errno::EINVAL));
+        }
+        Ok(())
+    }
+
+    fn put_data(&self, req: &mut Request) {
+        req.attr_u32(IFLA_GENEVE_ID, self.vni);
+        if let Some(ip) = self.remote {
+            match ip {
+                net::IpAddr::V4(..) => req.attr_ip(IFLA_GENEVE_REMOTE, ip),
+                net::IpAddr::V6(..) => req.attr_ip(IFLA_GENEVE_REMOTE6, ip),
+            }
+        }
+        req.attr(IFLA_GENEVE_PORT, &self.port.to_be_bytes());
+        req.attr_u8(IFLA_GENEVE_TTL, self.ttl.unwrap_or(0));
+    }
+
+    fn parse(data: &[u8]) -> Option<GeneveConfig> {
+        let mut ret = GeneveConfig {
+            vni: 0,
+            remote: None,
+            port: GENEVE_PORT,
+            ttl: None,
+        };
+        let mut has_id = false;
+
+        for (ty, val) in netlink::Attrs::new(data) {
+            match ty {
+                IFLA_GENEVE_ID => {
+                    ret.vni = netlink::parse_u32(val)?;
+                    has_id = true;
+                }
+                IFLA_GENEVE_REMOTE | IFLA_GENEVE_REMOTE6 => {
+                    ret.remote = netlink::parse_ip(val).filter(|ip| !ip.is_unspecified())
+                }
+                IFLA_GENEVE_PORT => ret.port = netlink::parse_u16(val).map(u16::from_be)?,
+                IFLA_GENEVE_TTL => ret.ttl = netlink::parse_u8(val).filter(|&ttl| ttl != 0),
+                _ => {}
+            }
+        }
+
+        if has_id {
+            Some(ret)
+        } else {
+            None
+        }
+    }
+}
+
+/// The type of a point-to-point IP tunnel.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TunnelKind {
+    /// GRE over IPv4, carrying IP packets.
+    Gre,
+
+    /// GRE over IPv4, carrying Ethernet frames.
+    Gretap,
+
+    /// IPv4 over IPv4.
+    Ipip,
+
+    /// IPv6 over IPv4.
+    Sit,
+
+    /// IPv4 or IPv6 over IPv6.
+    Ip6tnl,
+
+    /// GRE over IPv6, carrying IP packets.
+    Ip6gre,
+}
+
+impl TunnelKind {
+    /// Returns the name of this kind of tunnel, as used by the kernel.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            TunnelKind::Gre => "gre",
+            TunnelKind::Gretap => "gretap",
+            TunnelKind::Ipip => "ipip",
+            TunnelKind::Sit => "sit",
+            TunnelKind::Ip6tnl => "ip6tnl",
+            TunnelKind::Ip6gre => "ip6gre",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<TunnelKind> {
+        match name {
+            "gre" => Some(TunnelKind::Gre),
+            "gretap" => Some(TunnelKind::Gretap),
+            "ipip" => Some(TunnelKind::Ipip),
+            "sit" => Some(TunnelKind::Sit),
+            "ip6tnl" => Some(TunnelKind::Ip6tnl),
+            "ip6gre" => Some(TunnelKind::Ip6gre),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this tunnel uses GRE, and therefore supports keys.
+    pub fn is_gre(&self) -> bool {
+        match *self {
+            TunnelKind::Gre | TunnelKind::Gretap | TunnelKind::Ip6gre => true,
+            TunnelKind::Ipip | TunnelKind::Sit | TunnelKind::Ip6tnl => false,
+        }
+    }
+
+    /// Returns whether the outer packets of this tunnel are IPv6, and therefore have an
+    /// encapsulation limit.
+    pub fn is_ipv6(&self) -> bool {
+        match *self {
+            TunnelKind::Ip6tnl | TunnelKind::Ip6gre => true,
+            TunnelKind::Gre | TunnelKind::Gretap | TunnelKind::Ipip | TunnelKind::Sit => false,
+        }
+    }
+}
+
+impl fmt::Display for TunnelKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The settings of a point-to-point IP tunnel.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct TunnelConfig {
+    /// The type of tunnel.
+    pub kind: TunnelKind,
+
+    /// The local endpoint of the tunnel.
+    pub local: Option<net::IpAddr>,
+
+    /// The remote endpoint of the tunnel.
+    pub remote: Option<net::IpAddr>,
+
+    /// The index of the interface to send packets through.
+    pub device: Option<u32>,
+
+    /// The TTL (or hop limit) of outgoing packets.  `None` means that it is inherited from the
+    /// inner packet.
+    pub ttl: Option<u8>,
+
+    /// The GRE key, used in both directions.  Only valid for GRE tunnels.
+    pub key: Option<u32>,
+
+    /// The encapsulation limit of outgoing packets, or `None` if no limit is sent.  Only valid
+    /// for IPv6 tunnels.
+    pub encap_limit: Option<u8>,
+}
+
+impl TunnelConfig {
+    /// Returns the default settings for a tunnel of the given kind between two endpoints.
+    pub fn new(kind: TunnelKind, local: net::IpAddr, remote: net::IpAddr) -> TunnelConfig {
+        TunnelConfig {
+            kind,
+            local: Some(local),
+            remote: Some(remote),
+            device: None,
+            ttl: None,
+            key: None,
+            encap_limit: if kind.is_ipv6() {
+                Some(IPV6_DEFAULT_TNL_ENCAP_LIMIT)
+            } else {
+                None
+            },
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        // The endpoints must match the family of the outer packets.
+        let mut endpoints = self.local.iter().chain(self.remote.iter());
+        if endpoints.any(|ip| ip.is_ipv6() != self.kind.is_ipv6()) {
+            return Err(InterfacesError::Errno(Errno::EINVAL));
+        }
+        if self.key.is_some() && !self.kind.is_gre() {
+            return Err(InterfacesError::NotSupported("Keys on non-GRE tunnels"));
+        }
+        if self.encap_limit.is_some() && !self.kind.is_ipv6() {
+            return Err(InterfacesError::NotSupported(
+                "Encapsulation limit on IPv4 tunnels",
+            ));
+        }
+        Ok(())
+    }
+
+    fn put_data(&self, req: &mut Request) {
+        if self.kind.is_gre() {
+            if let Some(ip) = self.local {
+                req.attr_ip(IFLA_GRE_LOCAL, ip);
+            }
+            if let Some(ip) = self.remote {
+                req.attr_ip(IFLA_GRE_REMOTE, ip);
+            }
+            if let Some(device) = self.device {
+                req.attr_u32(IFLA_GRE_LINK, device);
+            }
+            req.attr_u8(IFLA_GRE_TTL, self.ttl.unwrap_or(0));
+            if let Some(key) = self.key {
+                req.attr(IFLA_GRE_IFLAGS, &GRE_KEY.to_be_bytes());
+                req.attr(IFLA_GRE_OFLAGS, &GRE_KEY.to_be_bytes());
+                req.attr(IFLA_GRE_IKEY, &key.to_be_bytes());
+                req.attr(IFLA_GRE_OKEY, &key.to_be_bytes());
+            }
+            if self.kind.is_ipv6() {
+                match self.encap_limit {
+                    Some(limit) => req.attr_u8(IFLA_GRE_ENCAP_LIMIT, limit),
+                    None => req.attr_u32(IFLA_GRE_FLAGS, IP6_TNL_F_IGN_ENCAP_LIMIT),
+                }
+            }
+        } else {
+            if let Some(ip) = self.local {
+                req.attr_ip(IFLA_IPTUN_LOCAL, ip);
+            }
+            if let Some(ip) = self.remote {
+                req.attr_ip(IFLA_IPTUN_REMOTE, ip);
+            }
+            if let Some(device) = self.device {
+                req.attr_u32(IFLA_IPTUN_LINK, device);
+            }
+            req.attr_u8(IFLA_IPTUN_TTL, self.ttl.unwrap_or(0));
+            if self.kind.is_ipv6() {
+                match self.encap_limit {
+                    Some(limit) => req.attr_u8(IFLA_IPTUN_ENCAP_LIMIT, limit),
+                    None => req.attr_u32(IFLA_IPTUN_FLAGS, IP6_TNL_F_IGN_ENCAP_LIMIT),
+                }
+            }
+        }
+    }
+
+    fn parse(kind: TunnelKind, data: &[u8]) -> TunnelConfig {
+        let mut ret = TunnelConfig {
+            kind,
+            local: None,
+            remote: None,
+            device: None,
+            ttl: None,
+            key: None,
+            encap_limit: None,
+        };
+
+        // Both families use the same layout for the attributes we care about, just with
+        // different numbers.
+        let (local, remote, link, ttl, encap_limit, flags) = if kind.is_gre() {
+            (
+                IFLA_GRE_LOCAL,
+                IFLA_GRE_REMOTE,
+                IFLA_GRE_LINK,
+                IFLA_GRE_TTL,
+                IFLA_GRE_ENCAP_LIMIT,
+                IFLA_GRE_FLAGS,
+            )
+        } else {
+            (
+                IFLA_IPTUN_LOCAL,
+                IFLA_IPTUN_REMOTE,
+                IFLA_IPTUN_LINK,
+                IFLA_IPTUN_TTL,
+                IFLA_IPTUN_ENCAP_LIMIT,
+                IFLA_IPTUN_FLAGS,
+            )
+        };
+
+        let mut limit = None;
+        let mut tnl_flags = 0;
+        let mut has_key = false;
+        for (ty, val) in netlink::Attrs::new(data) {
+            if ty == local {
+                ret.local = netlink::parse_ip(val).filter(|ip| !ip.is_unspecified());
+            } else if ty == remote {
+                ret.remote = netlink::parse_ip(val).filter(|ip| !ip.is_unspecified());
+            } else if ty == link {
+                ret.device = netlink::parse_u32(val).filter(|&i| i != 0);
+            } else if ty == ttl {
+                ret.ttl = netlink::parse_u8(val).filter(|&ttl| ttl != 0);
+            } else if ty == encap_limit {
+                limit = netlink::parse_u8(val);
+            } else if ty == flags && kind.is_ipv6() {
+                // SIT sends a 16-bit value here, so only read it for IPv6 tunnels.
+                tnl_flags = netlink::parse_u32(val).unwrap_or(0);
+            } else if kind.is_gre() && ty == IFLA_GRE_OFLAGS {
+                let gre_flags = netlink::parse_u16(val).map(u16::from_be).unwrap_or(0);
+                has_key = gre_flags & GRE_KEY != 0;
+            } else if kind.is_gre() && ty == IFLA_GRE_OKEY {
+                ret.key = netlink::parse_u32(val).map(u32::from_be);
+            }
+        }
+
+        if !has_key {
+            ret.key = None;
+        }
+        if kind.is_ipv6() && tnl_flags & IP6_TNL_F_IGN_ENCAP_LIMIT == 0 {
+            ret.encap_limit = limit;
+        }
+
+        ret
+    }
+}
+
+/// `LinkKind` represents the type of a virtual link (the `IFLA_INFO_KIND` attribute), along with
+/// any type-specific settings that this crate knows how to read and write.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum LinkKind {
+    /// A macvlan link, in the given mode.
+    Macvlan(MacvlanMode),
+
+    /// An ipvlan link, in the given mode.
+    Ipvlan(IpvlanMode),
+
+    /// A VXLAN overlay link.
+    Vxlan(VxlanConfig),
+
+    /// A Geneve overlay link.
+    Geneve(GeneveConfig),
+
+    /// A point-to-point IP tunnel.
+    Tunnel(TunnelConfig),
+
+    /// A VRF (virtual routing and forwarding) device, bound to the given routing table.
+    Vrf(u32),
+
+    /// Any other kind of link.  The interior string is the kind as reported by the kernel (e.g.
+    /// `"veth"` or `"bridge"`).
+    Other(String),
+}
+
+impl LinkKind {
+    /// Returns the name of this kind, as used by the kernel (and `ip link add ... type <name>`).
+    pub fn name(&self) -> &str {
+        match *self {
+            LinkKind::Macvlan(..) => "macvlan",
+            LinkKind::Ipvlan(..) => "ipvlan",
+            LinkKind::Vxlan(..) => "vxlan",
+            LinkKind::Geneve(..) => "geneve",
+            LinkKind::Tunnel(ref config) => config.kind.name(),
+            LinkKind::Vrf(..) => "vrf",
+            LinkKind::Other(ref s) => s,
+        }
+    }
+
+    /// Appends the `IFLA_LINKINFO` attribute describing this kind to a request.
+    fn put_linkinfo(&self, req: &mut Request) {
+        let info = req.begin_nested(netlink::IFLA_LINKINFO);
+        req.attr_str(netlink::IFLA_INFO_KIND, self.name());
+
+        match *self {
+            LinkKind::Macvlan(mode) => {
+                let data = req.begin_nested(netlink::IFLA_INFO_DATA);
+                req.attr_u32(IFLA_MACVLAN_MODE, mode.to_raw());
+                req.end_nested(data);
+            }
+            LinkKind::Ipvlan(mode) => {
+                let data = req.begin_nested(netlink::IFLA_INFO_DATA);
+                req.attr_u16(IFLA_IPVLAN_MODE, mode.to_raw());
+                req.end_nested(data);
+            }
+            LinkKind::Vxlan(ref config) => {
+                let data = req.begin_nested(netlink::IFLA_INFO_DATA);
+                config.put_data(req);
+                req.end_nested(data);
+            }
+            LinkKind::Geneve(ref config) => {
+                let data = req.begin_nested(netlink::IFLA_INFO_DATA);
+                config.put_data(req);
+                req.end_nested(data);
+            }
+            LinkKind::Tunnel(ref config) => {
+                let data = req.begin_nested(netlink::IFLA_INFO_DATA);
+                config.put_data(req);
+                req.end_nested(data);
+            }
+            LinkKind::Vrf(table) => {
+                let data = req.begin_nested(netlink::IFLA_INFO_DATA);
+                req.attr_u32(IFLA_VRF_TABLE, table);
+                req.end_nested(data);
+            }
+            LinkKind::Other(..) => {}
+        }
+
+        req.end_nested(info);
+    }
+
+    /// Parses the contents of an `IFLA_LINKINFO` attribute.
+    fn parse_linkinfo(buf: &[u8]) -> Option<LinkKind> {
+        let mut kind = None;
+        let mut data: &[u8] = &[];
+        for (ty, val) in netlink::Attrs::new(buf) {
+            match ty {
+                netlink::IFLA_INFO_KIND => kind = netlink::parse_str(val),
+                netlink::IFLA_INFO_DATA => data = val,
+                _ => {}
+            }
+        }
+
+        let kind = kind?;
+        let attrs = netlink::Attrs::new(data);
+        let parsed = match &*kind {
+            "macvlan" => attrs
+                .filter(|&(ty, _)| ty == IFLA_MACVLAN_MODE)
+                .filter_map(|(_, val)| netlink::parse_u32(val))
+                .filter_map(MacvlanMode::from_raw)
+                .map(LinkKind::Macvlan)
+                .next(),
+            "ipvlan" => attrs
+                .filter(|&(ty, _)| ty == IFLA_IPVLAN_MODE)
+                .filter_map(|(_, val)| netlink::parse_u16(val))
+                .filter_map(IpvlanMode::from_raw)
+                .map(LinkKind::Ipvlan)
+                .next(),
+            "vxlan" => VxlanConfig::parse(data).map(LinkKind::Vxlan),
+            "geneve" => GeneveConfig::parse(data).map(LinkKind::Geneve),
+            "vrf" => attrs
+                .filter(|&(ty, _)| ty == IFLA_VRF_TABLE)
+                .filter_map(|(_, val)| netlink::parse_u32(val))
+                .map(LinkKind::Vrf)
+                .next(),
+            name => TunnelKind::from_name(name)
+                .map(|kind| LinkKind::Tunnel(TunnelConfig::parse(kind, data))),
+        };
+
+        Some(parsed.unwrap_or(LinkKind::Other(kind)))
+    }
+}
+
+impl fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LinkKind::Macvlan(mode) => write!(f, "macvlan mode {}", mode),
+            LinkKind::Ipvlan(mode) => write!(f, "ipvlan mode {}", mode),
+            LinkKind::Vxlan(ref config) => write!(f, "vxlan id {}", config.vni),
+            LinkKind::Geneve(ref config) => write!(f, "geneve id {}", config.vni),
+            LinkKind::Tunnel(ref config) => write!(f, "{}", config.kind),
+            LinkKind::Vrf(table) => write!(f, "vrf table {}", table),
+            LinkKind::Other(ref s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// This structure contains the link-level information that the kernel reports for an interface.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct LinkInfo {
+    /// The index of the interface.
+    pub index: u32,
+
+    /// The name of the interface.
+    pub name: String,
+
+    /// The hardware type of the link (the `ifi_type` field).
+    pub link_type: LinkType,
+
+    /// The link-layer address of the interface (the `IFLA_ADDRESS` attribute), or an empty
+    /// address if it has none.
+    pub address: Vec<u8>,
+
+    /// The kind of link, if the interface has one.  Physical devices generally do not.
+    pub kind: Option<LinkKind>,
+
+    /// The index of the interface this link is stacked on (the `IFLA_LINK` attribute), e.g. the
+    /// parent of a macvlan or ipvlan link, or the peer of a veth link.
+    pub parent: Option<u32>,
+
+    /// The id of the namespace that `parent` lives in (the `IFLA_LINK_NETNSID` attribute), if it
+    /// is not the namespace of this interface.  See `Netns::id`.
+    pub link_netnsid: Option<i32>,
+
+    /// The index of the master device this interface is enslaved to (the `IFLA_MASTER`
+    /// attribute), e.g. a bridge or a VRF.
+    pub master: Option<u32>,
+}
+
+impl LinkInfo {
+    fn from_message(msg: &Message) -> Option<LinkInfo> {
+        if msg.payload.len() < netlink::IFINFOMSG_LEN {
+            return None;
+        }
+
+        let index = netlink::parse_u32(&msg.payload[4..8])?;
+        let mut ret = LinkInfo {
+            index,
+            name: String::new(),
+            link_type: LinkType::from_raw(netlink::parse_u16(&msg.payload[2..4])?),
+            address: vec![],
+            kind: None,
+            parent: None,
+            link_netnsid: None,
+            master: None,
+        };
+
+        for (ty, val) in msg.attrs(netlink::IFINFOMSG_LEN) {
+            match ty {
+                netlink::IFLA_ADDRESS => ret.address = val.to_vec(),
+                netlink::IFLA_IFNAME => ret.name = netlink::parse_str(val).unwrap_or_default(),
+                netlink::IFLA_LINK => ret.parent = netlink::parse_u32(val),
+                IFLA_LINK_NETNSID => ret.link_netnsid = netlink::parse_i32(val),
+                netlink::IFLA_LINKINFO => ret.kind = LinkKind::parse_linkinfo(val),
+                netlink::IFLA_MASTER => {
+                    ret.master = netlink::parse_u32(val).filter(|&master| master != 0)
+                }
+                _ => {}
+            }
+        }
+
+        // Devices without a parent report themselves.
+        if ret.link_netnsid.is_none() && ret.parent == Some(index) {
+            ret.parent = None;
+        }
+
+        Some(ret)
+    }
+}
+
+/// The other end of a veth pair, as returned by `Interface::veth_peer`.
+#[derive(Debug)]
+pub struct VethPeer {
+    /// The index of the peer, within its own namespace.
+    pub index: u32,
+
+    /// The id of the namespace the peer lives in, if it is not the namespace of the interface it
+    /// was looked up from.
+    pub netnsid: Option<i32>,
+
+    /// The namespace the peer lives in, if it is not the namespace of the interface it was looked
+    /// up from and it could be found.
+    pub netns: Option<Netns>,
+
+    /// The peer itself, if it could be found.
+    pub interface: Option<Interface>,
+}
+
+impl Interface {
+    /// Retrieves the link-level information for this interface, such as its kind and its parent.
+    pub fn link_info(&self) -> Result<LinkInfo> {
+        let mut sock = self.netlink_socket(netlink::NETLINK_ROUTE)?;
+        get_link(&mut sock, self.index()?)
+    }
+
+    /// Returns the link-level information that was read together with this interface by
+    /// `get_all` or `get_by_name`, e.g. the mode and parent of a macvlan link.  Unlike
+    /// `link_info`, this does not ask the kernel again, so it does not reflect later changes.
+    /// This is `None` if rtnetlink could not be used.
+    pub fn link(&self) -> Option<&LinkInfo> {
+        self.link.as_ref()
+    }
+
+    /// Returns the hardware type of this interface, e.g. `LinkType::Ether` for Ethernet-like
+    /// links or `LinkType::None` for TUN devices.  Wireless links outside of monitor mode present
+    /// themselves as Ethernet to the kernel; they are reported as `LinkType::Ieee80211` if sysfs
+    /// has a `wireless` or `phy80211` entry for them.
+    pub fn link_type(&self) -> Result<LinkType> {
+        self.link_type_in(Path::new(SYSFS_NET))
+    }
+
+    fn link_type_in(&self, sysfs: &Path) -> Result<LinkType> {
+        let link_type = self.link_info()?.link_type;
+        if link_type == LinkType::Ether
+            && (self.has_sysfs_entry(sysfs, "wireless")?
+                || self.has_sysfs_entry(sysfs, "phy80211")?)
+        {
+            return Ok(LinkType::Ieee80211);
+        }
+        Ok(link_type)
+    }
+
+    /// Returns the kind of link this interface is, as reported by the kernel (e.g. `"veth"`,
+    /// `"bridge"` or `"wireguard"`).  Physical devices and the loopback interface have none.
+    pub fn kind(&self) -> Result<Option<String>> {
+        Ok(self.link_info()?.kind.map(|kind| kind.name().to_string()))
+    }
+
+    /// Returns whether this interface is backed by a hardware device (e.g. a PCI or USB network
+    /// card), i.e. it has a `device` entry under `/sys/class/net/<name>`.  Since sysfs shows the
+    /// interfaces of the namespace it was mounted in, interfaces in other namespaces are
+    /// generally reported as not physical.
+    pub fn is_physical(&self) -> Result<bool> {
+        self.has_sysfs_entry(Path::new(SYSFS_NET), "device")
+    }
+
+    /// Returns whether `<sysfs>/<name>/<entry>` exists and belongs to this interface.
+    fn has_sysfs_entry(&self, sysfs: &Path, entry: &str) -> Result<bool> {
+        let dir = sysfs.join(&self.name);
+        if fs::symlink_metadata(dir.join(entry)).is_err() {
+            return Ok(false);
+        }
+
+        // Make sure that this is not an interface of the same name in another namespace.
+        let index = fs::read_to_string(dir.join("ifindex"))?;
+        Ok(index.trim().parse() == Ok(self.index()?))
+    }
+
+    /// Returns the interface this one is stacked on (e.g. the parent of a macvlan link), if any.
+    /// Parents in other namespaces are not returned; see `veth_peer` for those.
+    pub fn parent(&self) -> Result<Option<Interface>> {
+        let info = self.link_info()?;
+        match info.parent {
+            Some(index) if info.link_netnsid.is_none() => {
+                self.in_netns(|| Interface::get_by_index(index))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Creates a new veth pair, with the two ends named `name` and `peer_name`, and returns both.
+    /// Either end may then be moved into another namespace with `move_to_netns`.
+    pub fn create_veth(name: &str, peer_name: &str) -> Result<(Interface, Interface)> {
+        let mut req = Request::link(
+            netlink::RTM_NEWLINK,
+            netlink::NLM_F_CREATE | netlink::NLM_F_EXCL,
+            0,
+        );
+        req.attr_str(netlink::IFLA_IFNAME, name);
+
+        let info = req.begin_nested(netlink::IFLA_LINKINFO);
+        req.attr_str(netlink::IFLA_INFO_KIND, "veth");
+        let data = req.begin_nested(netlink::IFLA_INFO_DATA);
+        let peer = req.begin_nested(VETH_INFO_PEER);
+        // The peer is described by its own `ifinfomsg`, followed by its attributes.
+        for _ in 0..netlink::IFINFOMSG_LEN / 4 {
+            req.put_u32(0);
+        }
+        req.attr_str(netlink::IFLA_IFNAME, peer_name);
+        req.end_nested(peer);
+        req.end_nested(data);
+        req.end_nested(info);
+
+        Socket::route()?.request(req)?;
+
+        match (
+            Interface::get_by_name(name)?,
+            Interface::get_by_name(peer_name)?,
+        ) {
+            (Some(iface), Some(peer)) => Ok((iface, peer)),
+            _ => Err(InterfacesError::Errno(Errno::ENODEV)),
+        }
+    }
+
+    /// Returns the other end of this veth pair, or `None` if this is not a veth link.  If the peer
+    /// lives in another namespace, that namespace is searched for among those returned by
+    /// `Netns::list`.
+    ///
+    /// This works from either end, so it can both find the container side of a host veth, and
+    /// find the host side of a container's interface when called on an `Interface` obtained
+    /// inside `Netns::run`.
+    pub fn veth_peer(&self) -> Result<Option<VethPeer>> {
+        let info = self.link_info()?;
+        let index = match (info.kind, info.parent) {
+            (Some(ref kind), Some(index)) if kind.name() == "veth" => index,
+            _ => return Ok(None),
+        };
+
+        let (netns, interface) = match info.link_netnsid {
+            None => (None, self.in_netns(|| Interface::get_by_index(index))?),
+            Some(id) => match self.in_netns(|| Netns::from_id(id))? {
+                Some(netns) => {
+                    let iface = netns.run(|| Interface::get_by_index(index))??;
+                    (Some(netns), iface)
+                }
+                None => (None, None),
+            },
+        };
+
+        Ok(Some(VethPeer {
+            index,
+            netnsid: info.link_netnsid,
+            netns,
+            interface,
+        }))
+    }
+
+    /// Creates a new macvlan link with the given name on top of `parent`, and returns it.
+    pub fn create_macvlan(name: &str, parent: &Interface, mode: MacvlanMode) -> Result<Interface> {
+        create_link(name, Some(parent.index()?), &LinkKind::Macvlan(mode))
+    }
+
+    /// Creates a new ipvlan link with the given name on top of `parent`, and returns it.
+    pub fn create_ipvlan(name: &str, parent: &Interface, mode: IpvlanMode) -> Result<Interface> {
+        create_link(name, Some(parent.index()?), &LinkKind::Ipvlan(mode))
+    }
+
+    /// Creates a new VXLAN link with the given name and settings, and returns it.  Returns
+    /// `EINVAL` if the VNI does not fit in 24 bits, both a remote and a group are given, the
+    /// remote is a multicast address or the group is not, or the addresses are of different
+    /// families.
+    pub fn create_vxlan(name: &str, config: &VxlanConfig) -> Result<Interface> {
+        config.validate()?;
+        create_link(name, None, &LinkKind::Vxlan(*config))
+    }
+
+    /// Creates a new Geneve link with the given name and settings, and returns it.  Returns
+    /// `EINVAL` if the VNI does not fit in 24 bits or the remote is a multicast address.
+    pub fn create_geneve(name: &str, config: &GeneveConfig) -> Result<Interface> {
+        config.validate()?;
+        create_link(name, None, &LinkKind::Geneve(*config))
+    }
+
+    /// Creates a new point-to-point IP tunnel with the given name and settings, and returns it.
+    pub fn create_tunnel(name: &str, config: &TunnelConfig) -> Result<Interface> {
+        config.validate()?;
+        create_link(name, None, &LinkKind::Tunnel(*config))
+    }
+
+    /// Creates a new VRF device with the given name, bound to the routing table `table`, and
+    /// returns it.
+    pub fn create_vrf(name: &str, table: u32) -> Result<Interface> {
+        create_link(name, None, &LinkKind::Vrf(table))
+    }
+
+    /// Returns the master device this interface is enslaved to (e.g. a bridge or a VRF), if any.
+    pub fn master(&self) -> Result<Option<Interface>> {
+        match self.link_info()?.master {
+            Some(index) => self.in_netns(|| Interface::get_by_index(index)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the VRF this interface belongs to, if any.
+    pub fn vrf(&self) -> Result<Option<Interface>> {
+        let master = match self.master()? {
+            Some(master) => master,
+            None => return Ok(None),
+        };
+
+        match master.link_info()?.kind {
+            Some(LinkKind::Vrf(..)) => Ok(Some(master)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Enslaves this interface to `master` (e.g. moves it into a VRF or adds it to a bridge), or
+    /// releases it from its current master if `master` is `None`.
+    pub fn set_master(&self, master: Option<&Interface>) -> Result<()> {
+        let master = match master {
+            Some(master) => master.index()?,
+            None => 0,
+        };
+
+        let mut req = Request::link(netlink::RTM_SETLINK, 0, self.index()?);
+        req.attr_u32(netlink::IFLA_MASTER, master);
+        self.netlink_socket(netlink::NETLINK_ROUTE)?.request(req)
+    }
+
+    /// Deletes this interface from the system.  Only virtual links can be deleted.
+    pub fn delete(self) -> Result<()> {
+        let req = netlink::Request::link(netlink::RTM_DELLINK, 0, self.index()?);
+        self.netlink_socket(netlink::NETLINK_ROUTE)?.request(req)
+    }
+}
+
+/// Creates a new link of the given kind, optionally stacked on the interface with index `parent`.
+pub(crate) fn create_link(name: &str, parent: Option<u32>, kind: &LinkKind) -> Result<Interface> {
+    let mut req = Request::link(
+        netlink::RTM_NEWLINK,
+        netlink::NLM_F_CREATE | netlink::NLM_F_EXCL,
+        0,
+    );
+    req.attr_str(netlink::IFLA_IFNAME, name);
+    if let Some(parent) = parent {
+        req.attr_u32(netlink::IFLA_LINK, parent);
+    }
+    kind.put_linkinfo(&mut req);
+
+    Socket::route()?.request(req)?;
+
+    match Interface::get_by_name(name)? {
+        Some(iface) => Ok(iface),
+        None => Err(InterfacesError::Errno(Errno::ENODEV)),
+    }
+}
+
+/// Fills in the link information of the given interfaces from a single dump of all links.  The
+/// interfaces are left without it if rtnetlink is not available.
+pub(crate) fn fill_link_info(ifs: &mut [Interface]) -> Result<()> {
+    let req = Request::link(netlink::RTM_GETLINK, 0, 0);
+    let msgs = match Socket::route().and_then(|mut sock| sock.dump(req)) {
+        Ok(msgs) => msgs,
+        Err(ref e) if netlink::is_unavailable(e) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    let links: Vec<LinkInfo> = msgs
+        .iter()
+        .filter(|msg| msg.ty == netlink::RTM_NEWLINK)
+        .filter_map(LinkInfo::from_message)
+        .collect();
+
+    for iface in ifs.iter_mut() {
+        // IPv4 aliases (e.g. `eth0:1`) are not links of their own.
+        let name = base_name(&iface.name);
+        iface.link = links.iter().find(|link| link.name == name).cloned();
+
+        // The remote endpoint of a tunnel is its peer, so report it as the destination of the
+        // link-layer address and of the IP addresses of the same family, like the peer of other
+        // point-to-point interfaces.  Addresses that were given a peer of their own keep it.
+        let remote = match iface.link {
+            Some(LinkInfo {
+                kind: Some(LinkKind::Tunnel(ref config)),
+                ..
+            }) => config.remote,
+            _ => None,
+        };
+        if let Some(remote) = remote {
+            for addr in iface
+                .addresses
+                .iter_mut()
+                .filter(|a| has_no_peer(a, remote))
+            {
+                addr.destination = Some(remote);
+                #[allow(deprecated)]
+                {
+                    addr.hop = Some(NextHop::Destination(net::SocketAddr::new(remote, 0)));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns whether `addr` is the link-layer address or an IP address in the family of `remote`
+/// that no peer was reported for.  Without an explicit peer, `getifaddrs` reports the address
+/// itself as the destination of point-to-point links.
+fn has_no_peer(addr: &Address, remote: net::IpAddr) -> bool {
+    if addr.kind == Kind::Packet {
+        return true;
+    }
+    match addr.ip {
+        Some(ip) => {
+            ip.is_ipv6() == remote.is_ipv6()
+                && addr.broadcast.is_none()
+                && (addr.destination.is_none() || addr.destination == Some(ip))
+        }
+        None => false,
+    }
+}
+
+/// Fetches the link information for the interface with the given index.
+fn get_link(sock: &mut Socket, index: u32) -> Result<LinkInfo> {
+    let req = Request::link(netlink::RTM_GETLINK, 0, index);
+    let msgs = sock.transact(req)?;
+
+    msgs.iter()
+        .filter(|msg| msg.ty == netlink::RTM_NEWLINK)
+        .filter_map(LinkInfo::from_message)
+        .next()
+        .ok_or(InterfacesError::Errno(Errno::ENODEV))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+    use testutil;
+
+    #[test]
+    fn test_linkkind_roundtrip() {
+        let kinds = vec![
+            LinkKind::Macvlan(MacvlanMode::Vepa),
+            LinkKind::Ipvlan(IpvlanMode::L3s),
+            LinkKind::Vxlan(VxlanConfig {
+                group: Some("239.1.1.1".parse().unwrap()),
+                device: Some(3),
+                ttl: Some(10),
+                learning: false,
+                ..VxlanConfig::new(42)
+            }),
+            LinkKind::Geneve(GeneveConfig::new(7, "fd00::1".parse().unwrap())),
+            LinkKind::Tunnel(TunnelConfig {
+                key: Some(0x01020304),
+                ttl: Some(64),
+                ..TunnelConfig::new(
+                    TunnelKind::Gre,
+                    "10.0.0.1".parse().unwrap(),
+                    "10.0.0.2".parse().unwrap(),
+                )
+            }),
+            LinkKind::Tunnel(TunnelConfig {
+                encap_limit: None,
+                ..TunnelConfig::new(
+                    TunnelKind::Ip6tnl,
+                    "fd00::1".parse().unwrap(),
+                    "fd00::2".parse().unwrap(),
+                )
+            }),
+            LinkKind::Vrf(10),
+            LinkKind::Other("veth".to_string()),
+        ];
+
+        for kind in kinds {
+            let mut req = Request::new(netlink::RTM_NEWLINK, 0);
+            kind.put_linkinfo(&mut req);
+
+            let (_, info) = netlink::Attrs::new(req.body()).next().unwrap();
+            assert_eq!(LinkKind::parse_linkinfo(info), Some(kind));
+        }
+    }
+
+    #[test]
+    fn test_create_macvlan() {
+        testutil::in_netns(|| {
+            let parent = testutil::create_parent("parent0");
+            let mv = match testutil::supported(Interface::create_macvlan(
+                "mv0",
+                &parent,
+                MacvlanMode::Bridge,
+            )) {
+                Some(mv) => mv,
+                None => return,
+            };
+
+            let info = mv.link_info().unwrap();
+            assert_eq!(info.kind, Some(LinkKind::Macvlan(MacvlanMode::Bridge)));
+            assert_eq!(info.parent, Some(parent.index().unwrap()));
+            assert_eq!(mv.parent().unwrap(), Some(parent));
+
+            // The mode and parent are also read during enumeration.
+            let all = Interface::get_all().unwrap();
+            let found = all.iter().find(|i| i.name == "mv0").unwrap();
+            assert_eq!(found.link(), Some(&info));
+
+            mv.delete().unwrap();
+            assert!(Interface::get_by_name("mv0").unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_create_ipvlan() {
+        testutil::in_netns(|| {
+            let parent = testutil::create_parent("parent0");
+            let iv =
+                match testutil::supported(Interface::create_ipvlan("iv0", &parent, IpvlanMode::L3))
+                {
+                    Some(iv) => iv,
+                    None => return,
+                };
+
+            let info = iv.link_info().unwrap();
+            assert_eq!(info.kind, Some(LinkKind::Ipvlan(IpvlanMode::L3)));
+            assert_eq!(info.parent, Some(parent.index().unwrap()));
+        });
+    }
+
+    #[test]
+    fn test_create_vxlan() {
+        testutil::in_netns(|| {
+            let config = VxlanConfig {
+                remote: Some("10.0.0.1".parse().unwrap()),
+                local: Some("10.0.0.2".parse().unwrap()),
+                ttl: Some(64),
+                ..VxlanConfig::new(100)
+            };
+            let vx = match testutil::supported(Interface::create_vxlan("vx0", &config)) {
+                Some(vx) => vx,
+                None => return,
+            };
+
+            assert_eq!(vx.link_info().unwrap().kind, Some(LinkKind::Vxlan(config)));
+        });
+    }
+
+    #[test]
+    fn test_create_geneve() {
+        testutil::in_netns(|| {
+            let config = GeneveConfig::new(100, "10.0.0.1".parse().unwrap());
+            let gn = match testutil::supported(Interface::create_geneve("gn0", &config)) {
+                Some(gn) => gn,
+                None => return,
+            };
+
+            assert_eq!(gn.link_info().unwrap().kind, Some(LinkKind::Geneve(config)));
+        });
+    }
+
+    #[test]
+    fn test_create_tunnel() {
+        testutil::in_netns(|| {
+            let config = TunnelConfig {
+                ttl: Some(64),
+                ..TunnelConfig::new(
+                    TunnelKind::Ipip,
+                    "10.0.0.1".parse().unwrap(),
+                    "10.0.0.2".parse().unwrap(),
+                )
+            };
+            let tun = match testutil::supported(Interface::create_tunnel("tun0", &config)) {
+                Some(tun) => tun,
+                None => return,
+            };
+
+            assert_eq!(
+                tun.link_info().unwrap().kind,
+                Some(LinkKind::Tunnel(config))
+            );
+
+            // The remote endpoint is reported as the peer of the link.
+            let addr = tun
+                .addresses
+                .iter()
+                .find(|a| a.kind == Kind::Packet)
+                .unwrap();
+            assert_eq!(addr.destination, config.remote);
+
+            // So are the IPv4 addresses of the tunnel, but not the addresses of other families.
+            testutil::add_address(&tun, "10.1.0.1", 24);
+            testutil::add_address(&tun, "fd00::1", 64);
+            let tun = Interface::get_by_name("tun0").unwrap().unwrap();
+            for addr in tun.addresses.iter() {
+                match addr.kind {
+                    Kind::Ipv4 => assert_eq!(addr.destination, config.remote),
+                    Kind::Ipv6 => assert_ne!(addr.destination, config.remote),
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_tunnel_validate() {
+        let config = TunnelConfig {
+            key: Some(1),
+            ..TunnelConfig::new(
+                TunnelKind::Sit,
+                "10.0.0.1".parse().unwrap(),
+                "10.0.0.2".parse().unwrap(),
+            )
+        };
+        assert!(config.validate().is_err());
+
+        let v4: net::IpAddr = "10.0.0.1".parse().unwrap();
+        let v6: net::IpAddr = "fd00::1".parse().unwrap();
+        assert!(TunnelConfig::new(TunnelKind::Gre, v4, v4)
+            .validate()
+            .is_ok());
+        assert!(TunnelConfig::new(TunnelKind::Ip6gre, v6, v6)
+            .validate()
+            .is_ok());
+        for &(kind, local, remote) in &[
+            (TunnelKind::Gre, v6, v6),
+            (TunnelKind::Ipip, v4, v6),
+            (TunnelKind::Sit, v6, v4),
+            (TunnelKind::Ip6tnl, v4, v4),
+            (TunnelKind::Ip6gre, v6, v4),
+        ] {
+            match TunnelConfig::new(kind, local, remote).validate() {
+                Err(InterfacesError::Errno(Errno::EINVAL)) => {}
+                res => panic!("unexpected result for {}: {:?}", kind, res),
+            }
+        }
+    }
+
+    #[test]
+    fn test_vxlan_validate() {
+        let config = VxlanConfig {
+            group: Some("239.1.1.1".parse().unwrap()),
+            ..VxlanConfig::new(1)
+        };
+        assert!(config.validate().is_ok());
+
+        let both = VxlanConfig {
+            remote: Some("10.0.0.1".parse().unwrap()),
+            ..config
+        };
+        match both.validate() {
+            Err(InterfacesError::Errno(Errno::EINVAL)) => {}
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        let unicast_group = VxlanConfig {
+            group: Some("10.0.0.1".parse().unwrap()),
+            ..VxlanConfig::new(1)
+        };
+        assert!(unicast_group.validate().is_err());
+        assert!(Interface::create_vxlan("vx0", &unicast_group).is_err());
+
+        let invalid = vec![
+            VxlanConfig {
+                remote: Some("239.1.1.1".parse().unwrap()),
+                ..VxlanConfig::new(1)
+            },
+            VxlanConfig {
+                local: Some("fd00::1".parse().unwrap()),
+                ..config
+            },
+            VxlanConfig {
+                remote: Some("10.0.0.1".parse().unwrap()),
+                local: Some("fd00::1".parse().unwrap()),
+                ..VxlanConfig::new(1)
+            },
+            VxlanConfig::new(1 << 24),
+        ];
+        for config in invalid {
+            match config.validate() {
+                Err(InterfacesError::Errno(Errno::EINVAL)) => {}
+                res => panic!("unexpected result for {:?}: {:?}", config, res),
+            }
+        }
+
+        let config = VxlanConfig {
+            remote: Some("fd00::2".parse().unwrap()),
+            local: Some("fd00::1".parse().unwrap()),
+            ..VxlanConfig::new(MAX_VNI)
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_geneve_validate() {
+        let config = GeneveConfig::new(MAX_VNI, "10.0.0.1".parse().unwrap());
+        assert!(config.validate().is_ok());
+
+        for config in &[
+            GeneveConfig::new(1 << 24, "10.0.0.1".parse().unwrap()),
+            GeneveConfig::new(1, "ff02::1".parse().unwrap()),
+        ] {
+            match config.validate() {
+                Err(InterfacesError::Errno(Errno::EINVAL)) => {}
+                res => panic!("unexpected result for {:?}: {:?}", config, res),
+            }
+        }
+        assert!(Interface::create_geneve(
+            "gnv0",
+            &GeneveConfig::new(1 << 24, "10.0.0.1".parse().unwrap())
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_set_master() {
+        testutil::in_netns(|| {
+            let bridge = create_link("br0", None, &LinkKind::Other("bridge".to_string())).unwrap();
+            let port = Interface::create_vxlan("vx0", &VxlanConfig::new(1)).unwrap();
+
+            port.set_master(Some(&bridge)).unwrap();
+            assert_eq!(
+                port.link_info().unwrap().master,
+                Some(bridge.index().unwrap())
+            );
+            assert_eq!(port.master().unwrap(), Some(bridge));
+            assert_eq!(port.vrf().unwrap(), None);
+
+            port.set_master(None).unwrap();
+            assert_eq!(port.master().unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn test_create_vrf() {
+        testutil::in_netns(|| {
+            let vrf = match testutil::supported(Interface::create_vrf("vrf0", 10)) {
+                Some(vrf) => vrf,
+                None => return,
+            };
+            assert_eq!(vrf.link_info().unwrap().kind, Some(LinkKind::Vrf(10)));
+
+            let port = Interface::create_vxlan("vx0", &VxlanConfig::new(1)).unwrap();
+            port.set_master(Some(&vrf)).unwrap();
+            assert_eq!(port.vrf().unwrap(), Some(vrf));
+        });
+    }
+
+    #[test]
+    fn test_create_veth() {
+        testutil::in_netns(|| {
+            let (veth, peer) = Interface::create_veth("veth0", "veth1").unwrap();
+            let link = veth.link().unwrap();
+            assert_eq!(link.kind, Some(LinkKind::Other("veth".to_string())));
+            assert_eq!(link.parent, Some(peer.index().unwrap()));
+            assert_eq!(veth.parent().unwrap(), Some(peer));
+
+            let found = veth.veth_peer().unwrap().unwrap();
+            assert_eq!(found.netnsid, None);
+            assert!(found.netns.is_none());
+            assert_eq!(found.interface.unwrap().name, "veth1");
+
+            let parent = testutil::create_parent("parent0");
+            assert!(parent.veth_peer().unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_link_classification() {
+        testutil::in_netns(|| {
+            let (veth, _) = Interface::create_veth("veth0", "veth1").unwrap();
+            assert_eq!(veth.link_type().unwrap(), LinkType::Ether);
+            assert_eq!(veth.kind().unwrap(), Some("veth".to_string()));
+            assert!(!veth.is_physical().unwrap());
+
+            let lo = Interface::get_by_name("lo").unwrap().unwrap();
+            assert_eq!(lo.link_type().unwrap(), LinkType::Loopback);
+            assert_eq!(lo.kind().unwrap(), None);
+            assert!(!lo.is_physical().unwrap());
+        });
+    }
+
+    #[test]
+    fn test_wireless_link_type() {
+        testutil::in_netns(|| {
+            let (veth, _) = Interface::create_veth("veth0", "veth1").unwrap();
+            let sysfs = env::temp_dir().join(format!("interfaces-sysfs-{}", process::id()));
+            let dir = sysfs.join("veth0");
+            fs::create_dir_all(&dir).unwrap();
+            assert_eq!(veth.link_type_in(&sysfs).unwrap(), LinkType::Ether);
+
+            // The entry only counts if it belongs to the interface with this index.
+            fs::create_dir(dir.join("phy80211")).unwrap();
+            fs::write(dir.join("ifindex"), "0\n").unwrap();
+            assert_eq!(veth.link_type_in(&sysfs).unwrap(), LinkType::Ether);
+
+            fs::write(dir.join("ifindex"), format!("{}\n", veth.index().unwrap())).unwrap();
+            assert_eq!(veth.link_type_in(&sysfs).unwrap(), LinkType::Ieee80211);
+
+            fs::remove_dir(dir.join("phy80211")).unwrap();
+            fs::create_dir(dir.join("wireless")).unwrap();
+            assert_eq!(veth.link_type_in(&sysfs).unwrap(), LinkType::Ieee80211);
+
+            fs::remove_dir_all(&sysfs).unwrap();
+        });
+    }
+
+    #[test]
+    fn test_veth_peer_across_netns() {
+        testutil::in_netns(|| {
+            let (host, ctr) = Interface::create_veth("host0", "eth0").unwrap();
+            let netns = Netns::new().unwrap();
+            let ctr = ctr.move_to_netns(&netns).unwrap();
+
+            // The container side, as seen from the host.
+            assert_eq!(host.parent().unwrap(), None);
+            let peer = host.veth_peer().unwrap().unwrap();
+            assert_eq!(peer.index, ctr.index().unwrap());
+            assert!(peer.netnsid.is_some());
+            assert_eq!(peer.netns, Some(netns.clone()));
+            assert_eq!(peer.interface.unwrap().name, "eth0");
+
+            // The host side, as seen from the container.
+            let ctr = netns.run(|| Interface::get_by_name("eth0")).unwrap();
+            let peer = ctr.unwrap().unwrap().veth_peer().unwrap().unwrap();
+            assert_eq!(peer.index, host.index().unwrap());
+            assert_eq!(peer.netns, Some(Netns::current().unwrap()));
+            assert_eq!(peer.interface.unwrap().name, "host0");
+        });
+    }
+}
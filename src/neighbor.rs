@@ -0,0 +1,540 @@
+//! The neighbor tables, which map the IP addresses of hosts on the same link to their hardware
+//! addresses (ARP for IPv4, NDP for IPv6).  Entries are read and changed over rtnetlink, in the
+//! namespace of the calling thread.  This is only available on Linux.
+
+use std::fmt;
+use std::net;
+
+use libc;
+use nix::errno::Errno;
+
+use netlink::{self, Message, Request, Socket};
+use {HardwareAddr, Interface, InterfacesError, Result};
+
+/// The length of `struct ndmsg`.
+const NDMSG_LEN: usize = 12;
+
+// Attributes of neighbor messages.
+const NDA_DST: u16 = 1;
+const NDA_LLADDR: u16 = 2;
+
+const NUD_INCOMPLETE: u16 = 0x01;
+const NUD_REACHABLE: u16 = 0x02;
+const NUD_STALE: u16 = 0x04;
+const NUD_DELAY: u16 = 0x08;
+const NUD_PROBE: u16 = 0x10;
+const NUD_FAILED: u16 = 0x20;
+const NUD_NOARP: u16 = 0x40;
+const NUD_PERMANENT: u16 = 0x80;
+const NUD_NONE: u16 = 0x00;
+
+bitflags! {
+    /// Represents a set of flags that describe a neighbor entry.  This corresponds to the
+    /// `ndm_flags` field of `struct ndmsg`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct NeighborFlags: u8 {
+        /// The entry is in use, and should be refreshed.
+        const NTF_USE = 0x01;
+
+        /// The entry belongs to the device itself (bridge FDB only).
+        const NTF_SELF = 0x02;
+
+        /// The entry belongs to the master device (bridge FDB only).
+        const NTF_MASTER = 0x04;
+
+        /// The entry is a proxy entry, answered on behalf of another host.
+        const NTF_PROXY = 0x08;
+
+        /// The entry was learned externally, e.g. by a control plane.
+        const NTF_EXT_LEARNED = 0x10;
+
+        /// The entry is offloaded to hardware.
+        const NTF_OFFLOADED = 0x20;
+
+        /// The neighbor is an IPv6 router.
+        const NTF_ROUTER = 0x80;
+    }
+}
+
+/// The state of a neighbor entry, as tracked by neighbor unreachability detection.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum NeighborState {
+    /// Address resolution is in progress.
+    Incomplete,
+
+    /// The neighbor was confirmed to be reachable recently.
+    Reachable,
+
+    /// The entry is valid, but the neighbor has not been confirmed to be reachable recently.
+    Stale,
+
+    /// The entry is valid, and a confirmation of reachability is pending.
+    Delay,
+
+    /// The neighbor is being probed for reachability.
+    Probe,
+
+    /// Address resolution failed.
+    Failed,
+
+    /// The entry is valid, and the link does not need address resolution.
+    Noarp,
+
+    /// The entry was configured statically, and never expires.
+    Permanent,
+
+    /// The entry has no state, e.g. because it is a proxy entry.
+    None,
+
+    /// A state that is not known to this crate.  The interior value is the numerical state.
+    Other(u16),
+}
+
+impl NeighborState {
+    fn to_raw(self) -> u16 {
+        match self {
+            NeighborState::Incomplete => NUD_INCOMPLETE,
+            NeighborState::Reachable => NUD_REACHABLE,
+            NeighborState::Stale => NUD_STALE,
+            NeighborState::Delay => NUD_DELAY,
+            NeighborState::Probe => NUD_PROBE,
+            NeighborState::Failed => NUD_FAILED,
+            NeighborState::Noarp => NUD_NOARP,
+            NeighborState::Permanent => NUD_PERMANENT,
+            NeighborState::None => NUD_NONE,
+            NeighborState::Other(v) => v,
+        }
+    }
+
+    fn from_raw(v: u16) -> NeighborState {
+        match v {
+            NUD_INCOMPLETE => NeighborState::Incomplete,
+            NUD_REACHABLE => NeighborState::Reachable,
+            NUD_STALE => NeighborState::Stale,
+            NUD_DELAY => NeighborState::Delay,
+            NUD_PROBE => NeighborState::Probe,
+            NUD_FAILED => NeighborState::Failed,
+            NUD_NOARP => NeighborState::Noarp,
+            NUD_PERMANENT => NeighborState::Permanent,
+            NUD_NONE => NeighborState::None,
+            v => NeighborState::Other(v),
+        }
+    }
+}
+
+impl fmt::Display for NeighborState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NeighborState::Incomplete => write!(f, "INCOMPLETE"),
+            NeighborState::Reachable => write!(f, "REACHABLE"),
+            NeighborState::Stale => write!(f, "STALE"),
+            NeighborState::Delay => write!(f, "DELAY"),
+            NeighborState::Probe => write!(f, "PROBE"),
+            NeighborState::Failed => write!(f, "FAILED"),
+            NeighborState::Noarp => write!(f, "NOARP"),
+            NeighborState::Permanent => write!(f, "PERMANENT"),
+            NeighborState::None => write!(f, "NONE"),
+            NeighborState::Other(v) => write!(f, "{:#x}", v),
+        }
+    }
+}
+
+/// A single entry of a neighbor table.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Neighbor {
+    /// The IP address of the neighbor.
+    pub ip: net::IpAddr,
+
+    /// The hardware address of the neighbor, if it is known and is a MAC address.
+    pub hardware_addr: Option<HardwareAddr>,
+
+    /// The index of the interface the neighbor is reachable on.  See `Neighbor::interface`.
+    pub ifindex: u32,
+
+    /// The state of the entry.
+    pub state: NeighborState,
+
+    /// The flags of the entry.
+    pub flags: NeighborFlags,
+}
+
+impl Neighbor {
+    /// Returns a permanent entry that maps `ip` to `hardware_addr`, i.e. a static ARP or NDP
+    /// entry.  Its `ifindex` has to be filled in before it is added, unless it is added through
+    /// `Interface::add_neighbor`.
+    pub fn new(ip: net::IpAddr, hardware_addr: HardwareAddr) -> Neighbor {
+        Neighbor {
+            ip,
+            hardware_addr: Some(hardware_addr),
+            ifindex: 0,
+            state: NeighborState::Permanent,
+            flags: NeighborFlags::empty(),
+        }
+    }
+
+    /// Returns a proxy entry for `ip`, for which this host answers ARP or NDP requests on behalf
+    /// of another host (`ip neigh add proxy`).  Proxy entries only take effect if proxying is
+    /// enabled on the interface, see `Interface::set_proxy_ndp`.
+    pub fn proxy(ip: net::IpAddr) -> Neighbor {
+        Neighbor {
+            ip,
+            hardware_addr: None,
+            ifindex: 0,
+            state: NeighborState::None,
+            flags: NeighborFlags::NTF_PROXY,
+        }
+    }
+
+    /// Returns the interface the neighbor is reachable on.  The interface is looked up in the
+    /// namespace of the calling thread.
+    pub fn interface(&self) -> Result<Option<Interface>> {
+        Interface::get_by_index(self.ifindex)
+    }
+
+    /// Builds a request of the given type that describes this entry.
+    fn request(&self, ty: u16, flags: u16) -> Result<Request> {
+        if self.ifindex == 0 {
+            return Err(InterfacesError::Errno(Errno::EINVAL));
+        }
+
+        let family = match self.ip {
+            net::IpAddr::V4(..) => libc::AF_INET,
+            net::IpAddr::V6(..) => libc::AF_INET6,
+        };
+
+        let mut req = Request::new(ty, flags);
+        req.put_u8(family as u8);
+        req.put_u8(0);
+        req.put_u16(0);
+        req.put_u32(self.ifindex);
+        req.put_u16(self.state.to_raw());
+        req.put_u8(self.flags.bits());
+        req.put_u8(0);
+
+        req.attr_ip(NDA_DST, self.ip);
+        if let Some(ref addr) = self.hardware_addr {
+            req.attr(NDA_LLADDR, addr.as_bytes());
+        }
+
+        Ok(req)
+    }
+
+    fn from_message(msg: &Message) -> Option<Neighbor> {
+        if msg.payload.len() < NDMSG_LEN {
+            return None;
+        }
+
+        let hdr = &msg.payload;
+        match hdr[0] as i32 {
+            libc::AF_INET | libc::AF_INET6 => {}
+            _ => return None,
+        }
+
+        let mut ip = None;
+        let mut hardware_addr = None;
+        for (ty, val) in msg.attrs(NDMSG_LEN) {
+            match ty {
+                NDA_DST => ip = netlink::parse_ip(val),
+                NDA_LLADDR if val.len() == 6 => {
+                    let mut bytes = [0; 6];
+                    bytes.copy_from_slice(val);
+                    hardware_addr = Some(HardwareAddr(bytes));
+                }
+                _ => {}
+            }
+        }
+
+        Some(Neighbor {
+            ip: ip?,
+            hardware_addr,
+            ifindex: netlink::parse_u32(&hdr[4..8])?,
+            state: NeighborState::from_raw(netlink::parse_u16(&hdr[8..10])?),
+            flags: NeighborFlags::from_bits_truncate(hdr[10]),
+        })
+    }
+
+    /// Returns whether `flush_neighbors` removes this entry.
+    fn is_dynamic(&self) -> bool {
+        match self.state {
+            NeighborState::Permanent | NeighborState::Noarp => false,
+            _ => !self.flags.contains(NeighborFlags::NTF_PROXY),
+        }
+    }
+}
+
+/// Returns the entries of the IPv4 and IPv6 neighbor tables of all interfaces.  Proxy entries
+/// are kept in a separate table, see `proxy_neighbors`.
+pub fn neighbors() -> Result<Vec<Neighbor>> {
+    dump_neighbors(&mut Socket::route()?, NeighborFlags::empty())
+}
+
+/// Returns the proxy entries of all interfaces.  These are added and deleted like any other
+/// entry, see `Neighbor::proxy`.
+pub fn proxy_neighbors() -> Result<Vec<Neighbor>> {
+    dump_neighbors(&mut Socket::route()?, NeighborFlags::NTF_PROXY)
+}
+
+/// Adds a new entry.  Fails with `EEXIST` if there already is one for the same address and
+/// interface.
+pub fn add_neighbor(neighbor: &Neighbor) -> Result<()> {
+    let flags = netlink::NLM_F_CREATE | netlink::NLM_F_EXCL;
+    Socket::route()?.request(neighbor.request(netlink::RTM_NEWNEIGH, flags)?)
+}
+
+/// Adds a new entry, or replaces the existing one for the same address and interface.
+pub fn replace_neighbor(neighbor: &Neighbor) -> Result<()> {
+    let flags = netlink::NLM_F_CREATE | netlink::NLM_F_REPLACE;
+    Socket::route()?.request(neighbor.request(netlink::RTM_NEWNEIGH, flags)?)
+}
+
+/// Deletes the entry for the address and interface of `neighbor`.
+pub fn delete_neighbor(neighbor: &Neighbor) -> Result<()> {
+    Socket::route()?.request(neighbor.request(netlink::RTM_DELNEIGH, 0)?)
+}
+
+/// Deletes the dynamic entries of all interfaces, like `ip neigh flush all`.  Permanent, `Noarp`
+/// and proxy entries are kept.
+pub fn flush_neighbors() -> Result<()> {
+    flush(&mut Socket::route()?, None)
+}
+
+impl Interface {
+    /// Returns the neighbor entries of this interface.
+    pub fn neighbors(&self) -> Result<Vec<Neighbor>> {
+        self.dump_neighbors(NeighborFlags::empty())
+    }
+
+    /// Returns the proxy entries of this interface.
+    pub fn proxy_neighbors(&self) -> Result<Vec<Neighbor>> {
+        self.dump_neighbors(NeighborFlags::NTF_PROXY)
+    }
+
+    /// Returns whether this interface answers ARP requests for addresses that it has a route to
+    /// through another interface (`net.ipv4.conf.<name>.proxy_arp`).
+    pub fn proxy_arp(&self) -> Result<bool> {
+        self.ipv4_conf().proxy_arp()
+    }
+
+    /// Enables or disables proxy ARP on this interface.
+    pub fn set_proxy_arp(&self, enabled: bool) -> Result<()> {
+        self.ipv4_conf().set_proxy_arp(enabled)
+    }
+
+    /// Returns whether this interface answers neighbor solicitations for the addresses of its
+    /// proxy entries (`net.ipv6.conf.<name>.proxy_ndp`).
+    pub fn proxy_ndp(&self) -> Result<bool> {
+        self.ipv6_conf().proxy_ndp()
+    }
+
+    /// Enables or disables proxy NDP on this interface.
+    pub fn set_proxy_ndp(&self, enabled: bool) -> Result<()> {
+        self.ipv6_conf().set_proxy_ndp(enabled)
+    }
+
+    /// Adds a neighbor entry on this interface, i.e. `neighbor` with its `ifindex` set to this
+    /// one.
+    pub fn add_neighbor(&self, neighbor: &Neighbor) -> Result<()> {
+        let flags = netlink::NLM_F_CREATE | netlink::NLM_F_EXCL;
+        self.neighbor_request(neighbor, netlink::RTM_NEWNEIGH, flags)
+    }
+
+    /// Adds or replaces a neighbor entry on this interface, like `replace_neighbor`.
+    pub fn replace_neighbor(&self, neighbor: &Neighbor) -> Result<()> {
+        let flags = netlink::NLM_F_CREATE | netlink::NLM_F_REPLACE;
+        self.neighbor_request(neighbor, netlink::RTM_NEWNEIGH, flags)
+    }
+
+    /// Deletes a neighbor entry on this interface, like `delete_neighbor`.
+    pub fn delete_neighbor(&self, neighbor: &Neighbor) -> Result<()> {
+        self.neighbor_request(neighbor, netlink::RTM_DELNEIGH, 0)
+    }
+
+    /// Deletes the dynamic neighbor entries of this interface, like `ip neigh flush dev`.
+    /// Permanent, `Noarp` and proxy entries are kept.
+    pub fn flush_neighbors(&self) -> Result<()> {
+        let index = self.index()?;
+        flush(
+            &mut self.netlink_socket(netlink::NETLINK_ROUTE)?,
+            Some(index),
+        )
+    }
+
+    fn dump_neighbors(&self, flags: NeighborFlags) -> Result<Vec<Neighbor>> {
+        let index = self.index()?;
+        let mut sock = self.netlink_socket(netlink::NETLINK_ROUTE)?;
+        Ok(dump_neighbors(&mut sock, flags)?
+            .into_iter()
+            .filter(|n| n.ifindex == index)
+            .collect())
+    }
+
+    fn neighbor_request(&self, neighbor: &Neighbor, ty: u16, flags: u16) -> Result<()> {
+        let neighbor = Neighbor {
+            ifindex: self.index()?,
+            ..*neighbor
+        };
+        let req = neighbor.request(ty, flags)?;
+        self.netlink_socket(netlink::NETLINK_ROUTE)?.request(req)
+    }
+}
+
+/// Dumps the entries of every family and interface.  If `flags` contains `NTF_PROXY`, the proxy
+/// entries are dumped instead of the regular ones.
+fn dump_neighbors(sock: &mut Socket, flags: NeighborFlags) -> Result<Vec<Neighbor>> {
+    let mut req = Request::new(netlink::RTM_GETNEIGH, 0);
+    req.put_u32(0);
+    req.put_u32(0);
+    req.put_u16(0);
+    req.put_u8(flags.bits());
+    req.put_u8(0);
+
+    let msgs = sock.dump(req)?;
+    Ok(msgs
+        .iter()
+        .filter(|msg| msg.ty == netlink::RTM_NEWNEIGH)
+        .filter_map(Neighbor::from_message)
+        .collect())
+}
+
+/// Deletes the dynamic entries of the interface with the given index, or of all interfaces.
+fn flush(sock: &mut Socket, index: Option<u32>) -> Result<()> {
+    for neighbor in dump_neighbors(sock, NeighborFlags::empty())? {
+        if !neighbor.is_dynamic() || index.is_some_and(|i| i != neighbor.ifindex) {
+            continue;
+        }
+
+        // The entry may have expired in the meantime.
+        match sock.request(neighbor.request(netlink::RTM_DELNEIGH, 0)?) {
+            Ok(()) | Err(InterfacesError::Errno(Errno::ENOENT)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutil;
+
+    #[test]
+    fn test_neighbor_roundtrip() {
+        let neighbors = vec![
+            Neighbor {
+                ifindex: 2,
+                ..Neighbor::new(
+                    "10.0.0.1".parse().unwrap(),
+                    HardwareAddr([2, 0, 0, 0, 0, 1]),
+                )
+            },
+            Neighbor {
+                ip: "fe80::1".parse().unwrap(),
+                hardware_addr: None,
+                ifindex: 3,
+                state: NeighborState::Stale,
+                flags: NeighborFlags::NTF_ROUTER,
+            },
+        ];
+
+        for neighbor in neighbors {
+            let req = neighbor.request(netlink::RTM_NEWNEIGH, 0).unwrap();
+            let msg = Message {
+                ty: netlink::RTM_NEWNEIGH,
+                payload: req.body().to_vec(),
+            };
+            assert_eq!(Neighbor::from_message(&msg), Some(neighbor));
+        }
+
+        let unbound = Neighbor::new("10.0.0.1".parse().unwrap(), HardwareAddr::zero());
+        assert!(unbound.request(netlink::RTM_NEWNEIGH, 0).is_err());
+    }
+
+    #[test]
+    fn test_change_neighbors() {
+        testutil::in_netns(|| {
+            let (mut veth, mut peer) = Interface::create_veth("veth0", "veth1").unwrap();
+            veth.set_up(true).unwrap();
+            peer.set_up(true).unwrap();
+
+            let mac = HardwareAddr([2, 0, 0, 0, 0, 2]);
+            let static_v4 = Neighbor::new("10.9.0.2".parse().unwrap(), mac);
+            let static_v6 = Neighbor::new("fd00::2".parse().unwrap(), mac);
+            veth.add_neighbor(&static_v4).unwrap();
+            veth.add_neighbor(&static_v6).unwrap();
+
+            let found = veth.neighbors().unwrap();
+            let index = veth.index().unwrap();
+            for neighbor in &[static_v4, static_v6] {
+                let expected = Neighbor {
+                    ifindex: index,
+                    ..*neighbor
+                };
+                assert!(found.contains(&expected), "{:?}", found);
+            }
+            assert_eq!(
+                found[0].interface().unwrap().map(|i| i.name.clone()),
+                Some("veth0".to_string())
+            );
+            match veth.add_neighbor(&static_v4) {
+                Err(InterfacesError::Errno(Errno::EEXIST)) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+
+            // Replacing it with a dynamic entry makes it go away when flushed.
+            let stale = Neighbor {
+                state: NeighborState::Stale,
+                ..static_v4
+            };
+            veth.replace_neighbor(&stale).unwrap();
+            assert!(veth
+                .neighbors()
+                .unwrap()
+                .iter()
+                .any(|n| n.ip == stale.ip && n.state == NeighborState::Stale));
+
+            veth.flush_neighbors().unwrap();
+            let found = veth.neighbors().unwrap();
+            assert!(found.iter().all(|n| n.ip != stale.ip));
+            assert!(found.iter().any(|n| n.ip == static_v6.ip));
+
+            veth.delete_neighbor(&static_v6).unwrap();
+            assert!(veth
+                .neighbors()
+                .unwrap()
+                .iter()
+                .all(|n| n.ip != static_v6.ip));
+        });
+    }
+
+    #[test]
+    fn test_proxy_neighbors() {
+        testutil::in_netns(|| {
+            // A VLAN-style name, whose settings live in a directory with a dot in its name.
+            let (veth, _) = Interface::create_veth("veth0.100", "veth1").unwrap();
+            let index = veth.index().unwrap();
+
+            assert!(!veth.proxy_arp().unwrap());
+            veth.set_proxy_arp(true).unwrap();
+            assert!(veth.proxy_arp().unwrap());
+            veth.set_proxy_ndp(true).unwrap();
+            assert!(veth.proxy_ndp().unwrap());
+
+            let proxy = Neighbor::proxy("2001:db8::5".parse().unwrap());
+            veth.add_neighbor(&proxy).unwrap();
+            let expected = Neighbor {
+                ifindex: index,
+                ..proxy
+            };
+            assert_eq!(veth.proxy_neighbors().unwrap(), vec![expected]);
+            assert!(proxy_neighbors().unwrap().contains(&expected));
+
+            // Proxy entries are neither regular entries nor flushed.
+            assert!(veth.neighbors().unwrap().iter().all(|n| n.ip != proxy.ip));
+            veth.flush_neighbors().unwrap();
+            assert_eq!(veth.proxy_neighbors().unwrap().len(), 1);
+
+            veth.delete_neighbor(&proxy).unwrap();
+            assert!(veth.proxy_neighbors().unwrap().is_empty());
+        });
+    }
+}
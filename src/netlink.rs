@@ -0,0 +1,528 @@
+//! A minimal netlink client, just large enough to drive the `rtnetlink` requests made by this
+//! crate.  Messages are built and parsed by hand in native byte order, the same way the kernel
+//! lays them out.
+
+use std::mem;
+use std::net;
+
+use libc::{self, c_int, c_void};
+use nix::errno::Errno;
+
+use error::InterfacesError;
+use Result;
+
+pub const NETLINK_ROUTE: c_int = 0;
+pub const NETLINK_GENERIC: c_int = 16;
+
+// Message types.
+pub const NLMSG_ERROR: u16 = 2;
+pub const NLMSG_DONE: u16 = 3;
+pub const RTM_NEWLINK: u16 = 16;
+pub const RTM_DELLINK: u16 = 17;
+pub const RTM_GETLINK: u16 = 18;
+pub const RTM_SETLINK: u16 = 19;
+pub const RTM_NEWADDR: u16 = 20;
+pub const RTM_GETADDR: u16 = 22;
+pub const RTM_NEWROUTE: u16 = 24;
+pub const RTM_DELROUTE: u16 = 25;
+pub const RTM_GETROUTE: u16 = 26;
+pub const RTM_NEWNEIGH: u16 = 28;
+pub const RTM_DELNEIGH: u16 = 29;
+pub const RTM_GETNEIGH: u16 = 30;
+pub const RTM_NEWRULE: u16 = 32;
+pub const RTM_DELRULE: u16 = 33;
+pub const RTM_GETRULE: u16 = 34;
+
+// Message flags.
+pub const NLM_F_REQUEST: u16 = 0x1;
+pub const NLM_F_ACK: u16 = 0x4;
+pub const NLM_F_ROOT: u16 = 0x100;
+pub const NLM_F_MATCH: u16 = 0x200;
+pub const NLM_F_DUMP: u16 = NLM_F_ROOT | NLM_F_MATCH;
+pub const NLM_F_REPLACE: u16 = 0x100;
+pub const NLM_F_EXCL: u16 = 0x200;
+pub const NLM_F_CREATE: u16 = 0x400;
+
+// The generic netlink controller, used to look up the ids of other families.
+pub const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+/// Size of `struct genlmsghdr`.
+pub const GENLMSGHDR_LEN: usize = 4;
+
+// Link attributes (`IFLA_*`).
+pub const IFLA_ADDRESS: u16 = 1;
+pub const IFLA_IFNAME: u16 = 3;
+pub const IFLA_LINK: u16 = 5;
+pub const IFLA_MASTER: u16 = 10;
+pub const IFLA_LINKINFO: u16 = 18;
+
+// Nested inside `IFLA_LINKINFO`.
+pub const IFLA_INFO_KIND: u16 = 1;
+pub const IFLA_INFO_DATA: u16 = 2;
+
+/// Size of `struct nlmsghdr`.
+const HEADER_LEN: usize = 16;
+
+/// Size of `struct ifinfomsg`.
+pub const IFINFOMSG_LEN: usize = 16;
+
+/// Attribute types may carry these two flags in their upper bits.
+const NLA_F_NESTED: u16 = 1 << 15;
+const NLA_TYPE_MASK: u16 = !(NLA_F_NESTED | (1 << 14));
+
+/// Rounds `len` up to the 4-byte alignment used for both messages and attributes.
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// An outgoing netlink message.  The fixed family header (e.g. `ifinfomsg`) is written with the
+/// `put_*` methods, followed by any number of attributes.
+pub struct Request {
+    buf: Vec<u8>,
+}
+
+impl Request {
+    /// Create a new request of the given message type.  `NLM_F_REQUEST` and `NLM_F_ACK` are
+    /// always set when the request is sent; `flags` holds any additional ones.
+    pub fn new(ty: u16, flags: u16) -> Request {
+        let mut buf = vec![0; HEADER_LEN];
+        buf[4..6].copy_from_slice(&ty.to_ne_bytes());
+        buf[6..8].copy_from_slice(&flags.to_ne_bytes());
+        Request { buf }
+    }
+
+    /// Create a request that starts with an `ifinfomsg` header for the given interface index.
+    pub fn link(ty: u16, flags: u16, index: u32) -> Request {
+        let mut req = Request::new(ty, flags);
+        req.put_u8(libc::AF_UNSPEC as u8);
+        req.put_u8(0);
+        req.put_u16(0);
+        req.put_u32(index);
+        req.put_u32(0);
+        req.put_u32(0);
+        req
+    }
+
+    /// Create a generic netlink request for the given family, starting with a `genlmsghdr`.
+    pub fn generic(family: u16, cmd: u8, version: u8, flags: u16) -> Request {
+        let mut req = Request::new(family, flags);
+        req.put_u8(cmd);
+        req.put_u8(version);
+        req.put_u16(0);
+        req
+    }
+
+    fn flags(&self) -> u16 {
+        u16::from_ne_bytes([self.buf[6], self.buf[7]])
+    }
+
+    pub fn put_u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub fn put_u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_ne_bytes());
+    }
+
+    pub fn put_u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_ne_bytes());
+    }
+
+    /// Append an attribute with the given raw payload.
+    pub fn attr(&mut self, ty: u16, data: &[u8]) {
+        let len = 4 + data.len();
+        self.buf.extend_from_slice(&(len as u16).to_ne_bytes());
+        self.buf.extend_from_slice(&ty.to_ne_bytes());
+        self.buf.extend_from_slice(data);
+        self.pad();
+    }
+
+    pub fn attr_u8(&mut self, ty: u16, v: u8) {
+        self.attr(ty, &[v]);
+    }
+
+    pub fn attr_u16(&mut self, ty: u16, v: u16) {
+        self.attr(ty, &v.to_ne_bytes());
+    }
+
+    pub fn attr_u32(&mut self, ty: u16, v: u32) {
+        self.attr(ty, &v.to_ne_bytes());
+    }
+
+    /// Append a NUL-terminated string attribute.
+    pub fn attr_str(&mut self, ty: u16, s: &str) {
+        let mut data = Vec::with_capacity(s.len() + 1);
+        data.extend_from_slice(s.as_bytes());
+        data.push(0);
+        self.attr(ty, &data);
+    }
+
+    /// Append an IP address attribute in network byte order, as 4 or 16 bytes.
+    pub fn attr_ip(&mut self, ty: u16, ip: net::IpAddr) {
+        match ip {
+            net::IpAddr::V4(ip) => self.attr(ty, &ip.octets()),
+            net::IpAddr::V6(ip) => self.attr(ty, &ip.octets()),
+        }
+    }
+
+    /// Start a nested attribute.  The returned offset must be handed back to `end_nested` once
+    /// all of the nested attributes have been appended.
+    pub fn begin_nested(&mut self, ty: u16) -> usize {
+        let start = self.buf.len();
+        self.attr(ty | NLA_F_NESTED, &[]);
+        start
+    }
+
+    /// Start a structure that begins with its own `u16` length, such as `struct rtnexthop`.  The
+    /// returned offset must be handed to `end_nested` once the structure is complete.
+    pub fn begin_struct(&mut self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn end_nested(&mut self, start: usize) {
+        let len = (self.buf.len() - start) as u16;
+        self.buf[start..start + 2].copy_from_slice(&len.to_ne_bytes());
+    }
+
+    /// Returns everything after the `nlmsghdr`.
+    #[cfg(test)]
+    pub fn body(&self) -> &[u8] {
+        &self.buf[HEADER_LEN..]
+    }
+
+    fn pad(&mut self) {
+        let len = align(self.buf.len());
+        self.buf.resize(len, 0);
+    }
+
+    fn finish(&mut self, seq: u32) -> &[u8] {
+        let len = self.buf.len() as u32;
+        let flags = self.flags() | NLM_F_REQUEST | NLM_F_ACK;
+        self.buf[0..4].copy_from_slice(&len.to_ne_bytes());
+        self.buf[6..8].copy_from_slice(&flags.to_ne_bytes());
+        self.buf[8..12].copy_from_slice(&seq.to_ne_bytes());
+        &self.buf
+    }
+}
+
+/// A single incoming netlink message.  `payload` contains everything after the `nlmsghdr`.
+pub struct Message {
+    pub ty: u16,
+    pub payload: Vec<u8>,
+}
+
+impl Message {
+    /// Returns the attributes that follow a fixed header of `header_len` bytes.
+    pub fn attrs(&self, header_len: usize) -> Attrs<'_> {
+        let start = align(header_len).min(self.payload.len());
+        Attrs::new(&self.payload[start..])
+    }
+}
+
+/// An iterator over a buffer of netlink attributes, yielding `(type, payload)` pairs.
+pub struct Attrs<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Attrs<'a> {
+    pub fn new(buf: &'a [u8]) -> Attrs<'a> {
+        Attrs { buf }
+    }
+}
+
+impl<'a> Iterator for Attrs<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<(u16, &'a [u8])> {
+        if self.buf.len() < 4 {
+            return None;
+        }
+
+        let len = u16::from_ne_bytes([self.buf[0], self.buf[1]]) as usize;
+        let ty = u16::from_ne_bytes([self.buf[2], self.buf[3]]);
+        if len < 4 || len > self.buf.len() {
+            return None;
+        }
+
+        let data = &self.buf[4..len];
+        self.buf = &self.buf[align(len).min(self.buf.len())..];
+        Some((ty & NLA_TYPE_MASK, data))
+    }
+}
+
+pub fn parse_u8(data: &[u8]) -> Option<u8> {
+    data.first().cloned()
+}
+
+pub fn parse_u16(data: &[u8]) -> Option<u16> {
+    if data.len() < 2 {
+        return None;
+    }
+    Some(u16::from_ne_bytes([data[0], data[1]]))
+}
+
+pub fn parse_u32(data: &[u8]) -> Option<u32> {
+    if data.len() < 4 {
+        return None;
+    }
+    Some(u32::from_ne_bytes([data[0], data[1], data[2], data[3]]))
+}
+
+pub fn parse_u64(data: &[u8]) -> Option<u64> {
+    if data.len() < 8 {
+        return None;
+    }
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&data[..8]);
+    Some(u64::from_ne_bytes(bytes))
+}
+
+pub fn parse_i32(data: &[u8]) -> Option<i32> {
+    parse_u32(data).map(|v| v as i32)
+}
+
+/// Parses a (possibly NUL-terminated) string attribute.
+pub fn parse_str(data: &[u8]) -> Option<String> {
+    let data = match data.iter().position(|&b| b == 0) {
+        Some(end) => &data[..end],
+        None => data,
+    };
+    String::from_utf8(data.to_vec()).ok()
+}
+
+/// Parses an address attribute, which the kernel sends as 4 or 16 bytes in network byte order.
+pub fn parse_ip(data: &[u8]) -> Option<net::IpAddr> {
+    match data.len() {
+        4 => {
+            let mut octets = [0; 4];
+            octets.copy_from_slice(data);
+            Some(net::IpAddr::V4(net::Ipv4Addr::from(octets)))
+        }
+        16 => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(data);
+            Some(net::IpAddr::V6(net::Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+/// Returns whether an error means that rtnetlink cannot be used at all, e.g. because netlink
+/// sockets are blocked by a sandbox.  Interfaces can still be enumerated through `getifaddrs` in
+/// that case, just without the extra information that is read over rtnetlink.
+pub fn is_unavailable(err: &InterfacesError) -> bool {
+    matches!(
+        *err,
+        InterfacesError::Errno(Errno::EPROTONOSUPPORT)
+            | InterfacesError::Errno(Errno::EAFNOSUPPORT)
+            | InterfacesError::Errno(Errno::EPERM)
+    )
+}
+
+/// A netlink socket.  Every request is acknowledged, and the replies (if any) are collected
+/// before the call returns.
+pub struct Socket {
+    fd: c_int,
+    seq: u32,
+}
+
+impl Socket {
+    /// Opens a new netlink socket for the given protocol.
+    pub fn new(protocol: c_int) -> Result<Socket> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                protocol,
+            )
+        };
+        if fd < 0 {
+            return Err(InterfacesError::last_os_error());
+        }
+
+        // Bind with a zero port, so that the kernel picks one for us.
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        let res = unsafe {
+            libc::bind(
+                fd,
+                &addr as *const _ as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            let err = InterfacesError::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(err);
+        }
+
+        Ok(Socket { fd, seq: 0 })
+    }
+
+    /// Opens a new `NETLINK_ROUTE` socket.
+    pub fn route() -> Result<Socket> {
+        Socket::new(NETLINK_ROUTE)
+    }
+
+    /// Looks up the id of the generic netlink family with the given name.  If the family is not
+    /// registered (e.g. its module is not loaded), this returns `NotSupported`.
+    pub fn resolve_family(&mut self, name: &'static str) -> Result<u16> {
+        let mut req = Request::generic(GENL_ID_CTRL, CTRL_CMD_GETFAMILY, 1, 0);
+        req.attr_str(CTRL_ATTR_FAMILY_NAME, name);
+
+        let msgs = match self.transact(req) {
+            Ok(msgs) => msgs,
+            Err(InterfacesError::Errno(Errno::ENOENT)) => {
+                return Err(InterfacesError::NotSupported(name))
+            }
+            Err(e) => return Err(e),
+        };
+
+        msgs.iter()
+            .flat_map(|msg| msg.attrs(GENLMSGHDR_LEN))
+            .filter(|&(ty, _)| ty == CTRL_ATTR_FAMILY_ID)
+            .filter_map(|(_, val)| parse_u16(val))
+            .next()
+            .ok_or(InterfacesError::NotSupported(name))
+    }
+
+    /// Sends the request and waits for the kernel to acknowledge it, discarding any replies.
+    pub fn request(&mut self, req: Request) -> Result<()> {
+        self.transact(req).map(|_| ())
+    }
+
+    /// Sends the request with `NLM_F_DUMP` set and returns every message of the dump.
+    pub fn dump(&mut self, mut req: Request) -> Result<Vec<Message>> {
+        let flags = req.flags() | NLM_F_DUMP;
+        req.buf[6..8].copy_from_slice(&flags.to_ne_bytes());
+        self.transact(req)
+    }
+
+    /// Sends the request and returns all replies up to the final acknowledgement (or the end of
+    /// a dump).  A non-zero error in the acknowledgement is turned into an `Err`.  Replies to a
+    /// plain `RTM_GET*` request arrive before the acknowledgement, so they are returned too.
+    pub fn transact(&mut self, mut req: Request) -> Result<Vec<Message>> {
+        self.seq = self.seq.wrapping_add(1);
+        let seq = self.seq;
+
+        let res = {
+            let buf = req.finish(seq);
+            unsafe { libc::send(self.fd, buf.as_ptr() as *const c_void, buf.len(), 0) }
+        };
+        if res < 0 {
+            return Err(InterfacesError::last_os_error());
+        }
+
+        let mut ret = vec![];
+        loop {
+            let buf = self.recv()?;
+            let mut rest = &buf[..];
+
+            while rest.len() >= HEADER_LEN {
+                let len = parse_u32(&rest[0..4]).unwrap() as usize;
+                let ty = parse_u16(&rest[4..6]).unwrap();
+                let msg_seq = parse_u32(&rest[8..12]).unwrap();
+                if len < HEADER_LEN || len > rest.len() {
+                    break;
+                }
+
+                let payload = &rest[HEADER_LEN..len];
+                rest = &rest[align(len).min(rest.len())..];
+
+                // Skip anything left over from an earlier request.
+                if msg_seq != seq {
+                    continue;
+                }
+
+                match ty {
+                    NLMSG_ERROR | NLMSG_DONE => {
+                        let err = parse_i32(payload).unwrap_or(0);
+                        if err < 0 {
+                            return Err(InterfacesError::Errno(Errno::from_i32(-err)));
+                        }
+                        return Ok(ret);
+                    }
+                    _ => ret.push(Message {
+                        ty,
+                        payload: payload.to_vec(),
+                    }),
+                }
+            }
+        }
+    }
+
+    fn recv(&mut self) -> Result<Vec<u8>> {
+        // Peek first, so that we know how large of a buffer we need.
+        let mut probe = [0u8; 1];
+        let len = unsafe {
+            libc::recv(
+                self.fd,
+                probe.as_mut_ptr() as *mut c_void,
+                probe.len(),
+                libc::MSG_PEEK | libc::MSG_TRUNC,
+            )
+        };
+        if len < 0 {
+            return Err(InterfacesError::last_os_error());
+        }
+
+        let mut buf = vec![0u8; len as usize];
+        let len = unsafe { libc::recv(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0) };
+        if len < 0 {
+            return Err(InterfacesError::last_os_error());
+        }
+
+        buf.truncate(len as usize);
+        Ok(buf)
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IFLA_MTU: u16 = 4;
+
+    #[test]
+    fn test_attrs_roundtrip() {
+        let mut req = Request::new(RTM_NEWLINK, 0);
+        req.attr_str(IFLA_IFNAME, "eth0");
+        let nest = req.begin_nested(IFLA_LINKINFO);
+        req.attr_str(IFLA_INFO_KIND, "veth");
+        req.end_nested(nest);
+        req.attr_u32(IFLA_MTU, 1500);
+
+        let attrs = Attrs::new(req.body()).collect::<Vec<_>>();
+        assert_eq!(attrs.len(), 3);
+        assert_eq!(attrs[0].0, IFLA_IFNAME);
+        assert_eq!(parse_str(attrs[0].1), Some("eth0".to_string()));
+        assert_eq!(attrs[1].0, IFLA_LINKINFO);
+        let nested = Attrs::new(attrs[1].1).collect::<Vec<_>>();
+        assert_eq!(parse_str(nested[0].1), Some("veth".to_string()));
+        assert_eq!(parse_u32(attrs[2].1), Some(1500));
+    }
+
+    #[test]
+    fn test_dump_links() {
+        let mut sock = Socket::route().unwrap();
+        let links = sock.dump(Request::link(RTM_GETLINK, 0, 0)).unwrap();
+        assert!(links.iter().all(|m| m.ty == RTM_NEWLINK));
+        assert!(!links.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_family() {
+        let mut sock = Socket::new(NETLINK_GENERIC).unwrap();
+        assert_eq!(sock.resolve_family("nlctrl").unwrap(), GENL_ID_CTRL);
+        assert!(sock.resolve_family("no-such-family").is_err());
+    }
+}
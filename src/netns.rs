@@ -0,0 +1,384 @@
+//! Network namespaces.  A `Netns` is a handle to a namespace, and `Netns::run` executes code
+//! inside of it on a helper thread, so the calling thread never switches namespaces.  This is only
+//! available on Linux.
+
+use std::cell::RefCell;
+use std::fs::{self, File, OpenOptions};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::os::unix::io::AsRawFd;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use libc;
+use nix::errno::Errno;
+use nix::mount::{self, MntFlags, MsFlags};
+use nix::sched::{self, CloneFlags};
+
+use netlink::{self, Request, Socket};
+use {Interface, InterfacesError, Result};
+
+/// The directory in which named namespaces are kept, as used by `ip netns`.
+pub const NETNS_RUN_DIR: &str = "/run/netns";
+
+/// The namespace of the calling thread.
+const THREAD_NETNS_PATH: &str = "/proc/thread-self/ns/net";
+
+const IFLA_NET_NS_FD: u16 = 28;
+
+const RTM_NEWNSID: u16 = 88;
+const RTM_GETNSID: u16 = 90;
+
+/// The length of `struct rtgenmsg`, padded to the netlink alignment.
+const RTGENMSG_LEN: usize = 4;
+
+// Attributes of `RTM_GETNSID` and `RTM_NEWNSID` messages.
+const NETNSA_NSID: u16 = 1;
+const NETNSA_FD: u16 = 3;
+
+thread_local! {
+    /// The namespace that `Netns::run` switched this thread into, if any.
+    static CURRENT: RefCell<Option<Netns>> = const { RefCell::new(None) };
+}
+
+/// A handle to a network namespace.  The namespace stays alive for at least as long as the
+/// handle does.  Two handles compare equal if they refer to the same namespace.
+#[derive(Debug, Clone)]
+pub struct Netns {
+    file: Arc<File>,
+    dev: u64,
+    ino: u64,
+}
+
+impl Netns {
+    /// Opens the namespace referred to by the given path, e.g. `/proc/<pid>/ns/net` or a file
+    /// that a namespace is bind-mounted on.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Netns> {
+        let file = File::open(path)?;
+        let meta = file.metadata()?;
+        Ok(Netns {
+            file: Arc::new(file),
+            dev: meta.dev(),
+            ino: meta.ino(),
+        })
+    }
+
+    /// Opens the named namespace with the given name, as created by `ip netns add`.
+    pub fn from_name(name: &str) -> Result<Netns> {
+        Netns::open(named_path(name)?)
+    }
+
+    /// Opens the namespace of the process with the given pid.
+    pub fn from_pid(pid: u32) -> Result<Netns> {
+        Netns::open(format!("/proc/{}/ns/net", pid))
+    }
+
+    /// Opens the namespace of the calling thread.
+    pub fn current() -> Result<Netns> {
+        Netns::open(THREAD_NETNS_PATH)
+    }
+
+    /// Creates a new, anonymous namespace.  It is destroyed once the last handle to it (and the
+    /// last process in it) goes away.
+    pub fn new() -> Result<Netns> {
+        in_new_netns(Netns::current)
+    }
+
+    /// Creates a new namespace with the given name, which persists until it is deleted with
+    /// `Netns::delete`.  This is equivalent to `ip netns add <name>`.
+    pub fn create(name: &str) -> Result<Netns> {
+        let path = named_path(name)?;
+
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o755)
+            .create(NETNS_RUN_DIR)?;
+
+        // The bind mount needs an existing file to mount over.
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        let res = in_new_netns(|| {
+            mount::mount(
+                Some(THREAD_NETNS_PATH),
+                &path,
+                None::<&str>,
+                MsFlags::MS_BIND,
+                None::<&str>,
+            )?;
+            Ok(())
+        });
+        if let Err(e) = res {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+
+        Netns::open(&path)
+    }
+
+    /// Deletes the named namespace with the given name.  The namespace itself lives on for as long
+    /// as any handles to it (or processes in it) remain.
+    pub fn delete(name: &str) -> Result<()> {
+        let path = named_path(name)?;
+
+        match mount::umount2(&path, MntFlags::MNT_DETACH) {
+            Ok(()) | Err(Errno::EINVAL) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    /// Returns all namespaces that can be found on this system: the named ones, those of all
+    /// processes and of the threads of this process, and those that this process holds handles
+    /// to.  Each namespace is only returned
+    /// once.  Namespaces that cannot be opened (e.g. because the process exited) are skipped.
+    pub fn list() -> Result<Vec<Netns>> {
+        let mut paths = vec![];
+
+        if let Ok(entries) = fs::read_dir(NETNS_RUN_DIR) {
+            paths.extend(entries.filter_map(|e| e.ok()).map(|e| e.path()));
+        }
+
+        for entry in fs::read_dir("/proc")? {
+            let entry = entry?;
+            if entry.file_name().to_str().is_some_and(is_pid) {
+                paths.push(entry.path().join("ns/net"));
+            }
+        }
+
+        for entry in fs::read_dir("/proc/self/task")? {
+            paths.push(entry?.path().join("ns/net"));
+        }
+
+        // Namespaces that no process is in, e.g. those created with `Netns::new`.
+        for entry in fs::read_dir("/proc/self/fd")? {
+            let path = entry?.path();
+            match fs::read_link(&path) {
+                Ok(ref target) if target.to_string_lossy().starts_with("net:[") => paths.push(path),
+                _ => {}
+            }
+        }
+
+        let mut ret: Vec<Netns> = vec![];
+        for path in paths {
+            if let Ok(netns) = Netns::open(&path) {
+                if !ret.contains(&netns) {
+                    ret.push(netns);
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Returns the id that the namespace of the calling thread has assigned to this namespace, if
+    /// any.  Ids are local to each namespace, and are what netlink uses to refer to other
+    /// namespaces, e.g. for the peer of a veth pair.
+    pub fn id(&self) -> Result<Option<i32>> {
+        let mut req = Request::new(RTM_GETNSID, 0);
+        req.put_u8(libc::AF_UNSPEC as u8);
+        req.put_u8(0);
+        req.put_u16(0);
+        req.attr_u32(NETNSA_FD, self.file.as_raw_fd() as u32);
+
+        let msgs = Socket::route()?.transact(req)?;
+        let id = msgs
+            .iter()
+            .filter(|msg| msg.ty == RTM_NEWNSID)
+            .flat_map(|msg| msg.attrs(RTGENMSG_LEN))
+            .filter(|&(ty, _)| ty == NETNSA_NSID)
+            .filter_map(|(_, val)| netlink::parse_i32(val))
+            .next();
+
+        // An id of -1 means that none has been assigned.
+        Ok(id.filter(|&id| id >= 0))
+    }
+
+    /// Finds the namespace that the namespace of the calling thread knows by the given id.
+    pub fn from_id(id: i32) -> Result<Option<Netns>> {
+        for netns in Netns::list()? {
+            if netns.id()? == Some(id) {
+                return Ok(Some(netns));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Runs `f` inside this namespace, and returns its result.  This happens on a helper thread,
+    /// so the namespace of the calling thread is never changed.  Any `Interface` that is looked up
+    /// or created inside of `f` remembers its namespace, and can still be used afterwards.
+    pub fn run<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce() -> T + Send,
+        T: Send,
+    {
+        thread::scope(|s| {
+            let handle = s.spawn(|| {
+                sched::setns(self.file.as_raw_fd(), CloneFlags::CLONE_NEWNET)?;
+                CURRENT.with(|cur| *cur.borrow_mut() = Some(self.clone()));
+                Ok(f())
+            });
+
+            match handle.join() {
+                Ok(ret) => ret,
+                Err(e) => panic::resume_unwind(e),
+            }
+        })
+    }
+
+    /// Returns the namespace that the calling thread was switched into by `Netns::run`, if any.
+    pub(crate) fn thread_current() -> Option<Netns> {
+        CURRENT.with(|cur| cur.borrow().clone())
+    }
+}
+
+impl PartialEq for Netns {
+    fn eq(&self, other: &Netns) -> bool {
+        self.dev == other.dev && self.ino == other.ino
+    }
+}
+
+impl Eq for Netns {}
+
+impl Interface {
+    /// Moves this interface into the given namespace, and returns it as seen from there.
+    pub fn move_to_netns(self, netns: &Netns) -> Result<Interface> {
+        let mut req = Request::link(netlink::RTM_SETLINK, 0, self.index()?);
+        req.attr_u32(IFLA_NET_NS_FD, netns.file.as_raw_fd() as u32);
+        self.netlink_socket(netlink::NETLINK_ROUTE)?.request(req)?;
+
+        let name = self.name.clone();
+        match netns.run(move || Interface::get_by_name(&name))?? {
+            Some(iface) => Ok(iface),
+            None => Err(InterfacesError::Errno(Errno::ENODEV)),
+        }
+    }
+
+    /// Runs `f` in the namespace this interface was found in.
+    pub(crate) fn in_netns<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T> + Send,
+        T: Send,
+    {
+        match self.netns {
+            Some(ref netns) => netns.run(f)?,
+            None => f(),
+        }
+    }
+
+    /// Opens a netlink socket in the namespace this interface was found in.
+    pub(crate) fn netlink_socket(&self, protocol: i32) -> Result<Socket> {
+        self.in_netns(|| Socket::new(protocol))
+    }
+}
+
+/// Runs `f` on a helper thread that has been moved into a new namespace.
+fn in_new_netns<F, T>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send,
+    T: Send,
+{
+    thread::scope(|s| {
+        let handle = s.spawn(|| {
+            sched::unshare(CloneFlags::CLONE_NEWNET)?;
+            f()
+        });
+
+        match handle.join() {
+            Ok(ret) => ret,
+            Err(e) => panic::resume_unwind(e),
+        }
+    })
+}
+
+fn is_pid(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Returns the path of the named namespace with the given name.
+fn named_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        return Err(InterfacesError::Errno(Errno::EINVAL));
+    }
+    Ok(Path::new(NETNS_RUN_DIR).join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use link::{self, LinkKind, VxlanConfig};
+    use testutil;
+
+    #[test]
+    fn test_run_in_netns() {
+        testutil::in_netns(|| {
+            let netns = Netns::new().unwrap();
+            let bridge = LinkKind::Other("bridge".to_string());
+            let mut br = netns
+                .run(|| link::create_link("br0", None, &bridge))
+                .unwrap()
+                .unwrap();
+
+            // The interface only exists in the other namespace, but can still be used from here.
+            assert!(Interface::get_by_name("br0").unwrap().is_none());
+            br.set_up(true).unwrap();
+            assert_eq!(br.link_info().unwrap().kind, Some(bridge));
+
+            let found = netns.run(|| Interface::get_by_name("br0")).unwrap();
+            assert!(found.unwrap().unwrap().is_up());
+        });
+    }
+
+    #[test]
+    fn test_move_to_netns() {
+        testutil::in_netns(|| {
+            let netns = Netns::new().unwrap();
+            let iface = Interface::create_vxlan("parent0", &VxlanConfig::new(1)).unwrap();
+
+            let moved = iface.move_to_netns(&netns).unwrap();
+            assert!(Interface::get_by_name("parent0").unwrap().is_none());
+            assert!(moved.link_info().is_ok());
+            moved.delete().unwrap();
+
+            let found = netns.run(|| Interface::get_by_name("parent0")).unwrap();
+            assert!(found.unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_netns_id() {
+        testutil::in_netns(|| {
+            let current = Netns::current().unwrap();
+            let netns = Netns::new().unwrap();
+            assert_ne!(current, netns);
+            assert_eq!(current, Netns::current().unwrap());
+
+            let list = Netns::list().unwrap();
+            assert!(list.contains(&current));
+            assert!(list.contains(&netns));
+
+            // Ids are only assigned on demand, e.g. when a veth peer is moved there.
+            assert_eq!(netns.id().unwrap(), None);
+            let (_, peer) = Interface::create_veth("veth0", "veth1").unwrap();
+            peer.move_to_netns(&netns).unwrap();
+
+            let id = netns.id().unwrap().unwrap();
+            assert_eq!(Netns::from_id(id).unwrap(), Some(netns));
+        });
+    }
+
+    #[test]
+    fn test_named_path() {
+        assert_eq!(
+            named_path("blue").unwrap(),
+            Path::new("/run/netns/blue").to_path_buf()
+        );
+        assert!(named_path("../etc").is_err());
+        assert!(named_path("").is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! IP networks, i.e. an address together with the length of its network prefix.
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use nix::errno::Errno;
+
+use {Address, InterfacesError, Result};
+
+/// An IP address with a prefix length, such as `10.0.0.2/24`.  The address does not have to be
+/// the network address itself; see `IpNetwork::network` for that.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    /// Creates a new network.  Returns `EINVAL` if the prefix is longer than the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<IpNetwork> {
+        if prefix_len > max_prefix_len(&addr) {
+            return Err(InterfacesError::Errno(Errno::EINVAL));
+        }
+
+        Ok(IpNetwork { addr, prefix_len })
+    }
+
+    /// Creates a new network from an address and its netmask.  Returns `EINVAL` if the netmask is
+    /// of a different family than the address, or is not contiguous (e.g. `255.0.255.0`).
+    pub fn with_netmask(addr: IpAddr, netmask: IpAddr) -> Result<IpNetwork> {
+        let prefix_len = match (addr, netmask) {
+            (IpAddr::V4(_), IpAddr::V4(mask)) => mask_prefix_len(u32::from(mask) as u128, 32),
+            (IpAddr::V6(_), IpAddr::V6(mask)) => mask_prefix_len(u128::from(mask), 128),
+            _ => None,
+        };
+
+        match prefix_len {
+            Some(prefix_len) => IpNetwork::new(addr, prefix_len),
+            None => Err(InterfacesError::Errno(Errno::EINVAL)),
+        }
+    }
+
+    /// Returns the address.
+    pub fn ip(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the length of the network prefix.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns the netmask, e.g. `255.255.255.0` for a `/24`.
+    pub fn netmask(&self) -> IpAddr {
+        self.addr_of(self.mask())
+    }
+
+    /// Returns the network address, i.e. the address with all bits after the prefix cleared.
+    ///
+    /// ```
+    /// # use interfaces::IpNetwork;
+    /// let net = IpNetwork::new("10.0.0.2".parse().unwrap(), 24).unwrap();
+    /// assert_eq!(net.network(), "10.0.0.0".parse::<std::net::IpAddr>().unwrap());
+    /// ```
+    pub fn network(&self) -> IpAddr {
+        self.addr_of(self.bits() & self.mask())
+    }
+
+    /// Returns the broadcast address, i.e. the address with all bits after the prefix set.  IPv6
+    /// has no broadcast addresses, so this always returns `None` for IPv6 networks.
+    pub fn broadcast(&self) -> Option<Ipv4Addr> {
+        match self.addr_of(self.bits() | self.host_mask()) {
+            IpAddr::V4(addr) => Some(addr),
+            IpAddr::V6(_) => None,
+        }
+    }
+
+    /// Returns whether the given address is in this network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        ip.is_ipv4() == self.addr.is_ipv4()
+            && (self.bits_of(ip) & self.mask()) == self.bits() & self.mask()
+    }
+
+    /// Returns an iterator over the addresses of the hosts in this network.  For IPv4 networks
+    /// larger than a `/31`, this skips the network and broadcast addresses.
+    pub fn hosts(&self) -> Hosts {
+        let first = self.bits() & self.mask();
+        let last = first | self.host_mask();
+        let range = if self.addr.is_ipv4() && self.prefix_len < 31 {
+            (first + 1, last - 1)
+        } else {
+            (first, last)
+        };
+
+        Hosts {
+            net: *self,
+            range: Some(range),
+        }
+    }
+
+    /// Returns the netmask as an integer, aligned like the result of `bits`.
+    fn mask(&self) -> u128 {
+        let all = u128::MAX >> (128 - max_prefix_len(&self.addr) as u32);
+        all & !self.host_mask()
+    }
+
+    /// Returns the inverse of the netmask, i.e. the host part of an address.
+    fn host_mask(&self) -> u128 {
+        let host_bits = (max_prefix_len(&self.addr) - self.prefix_len) as u32;
+        u128::MAX.checked_shr(128 - host_bits).unwrap_or(0)
+    }
+
+    fn bits(&self) -> u128 {
+        self.bits_of(self.addr)
+    }
+
+    fn bits_of(&self, ip: IpAddr) -> u128 {
+        match ip {
+            IpAddr::V4(ip) => u32::from(ip) as u128,
+            IpAddr::V6(ip) => u128::from(ip),
+        }
+    }
+
+    fn addr_of(&self, bits: u128) -> IpAddr {
+        match self.addr {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(bits as u32)),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(bits)),
+        }
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// An iterator over the host addresses of a network, returned by `IpNetwork::hosts`.
+#[derive(Debug, Clone)]
+pub struct Hosts {
+    net: IpNetwork,
+    range: Option<(u128, u128)>,
+}
+
+impl Iterator for Hosts {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let (next, last) = self.range?;
+        self.range = if next < last {
+            Some((next + 1, last))
+        } else {
+            None
+        };
+
+        Some(self.net.addr_of(next))
+    }
+}
+
+impl Address {
+    /// Returns the address with the prefix length of its netmask, e.g. `10.0.0.2/24`, or `None`
+    /// if this is not an IP address or it has no netmask.  Returns `EINVAL` if the netmask is not
+    /// contiguous.
+    pub fn network(&self) -> Result<Option<IpNetwork>> {
+        match (self.ip, self.netmask) {
+            (Some(ip), Some(netmask)) => IpNetwork::with_netmask(ip, netmask).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the prefix length of the netmask, e.g. `24` for `255.255.255.0`.  See
+    /// `Address::network`.
+    pub fn prefix_len(&self) -> Result<Option<u8>> {
+        self.network().map(|net| net.map(|net| net.prefix_len()))
+    }
+}
+
+fn max_prefix_len(addr: &IpAddr) -> u8 {
+    match *addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+/// Returns the number of leading ones of a `width`-bit netmask, or `None` if the ones are not
+/// contiguous.
+fn mask_prefix_len(mask: u128, width: u32) -> Option<u8> {
+    let mask = mask << (128 - width);
+    let ones = mask.leading_ones();
+    if ones == 128 || mask << ones == 0 {
+        Some(ones as u8)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Interface, Kind};
+
+    fn net(s: &str, prefix_len: u8) -> IpNetwork {
+        IpNetwork::new(s.parse().unwrap(), prefix_len).unwrap()
+    }
+
+    #[test]
+    fn test_with_netmask() {
+        let addr = "10.0.0.2".parse().unwrap();
+        for &(mask, len) in &[
+            ("255.255.255.0", 24),
+            ("0.0.0.0", 0),
+            ("255.255.255.255", 32),
+        ] {
+            let net = IpNetwork::with_netmask(addr, mask.parse().unwrap()).unwrap();
+            assert_eq!(net.prefix_len(), len);
+            assert_eq!(net.netmask(), mask.parse::<IpAddr>().unwrap());
+        }
+        for mask in &["255.0.255.0", "0.0.0.255", "ffff:ffff::"] {
+            assert!(IpNetwork::with_netmask(addr, mask.parse().unwrap()).is_err());
+        }
+
+        let addr = "2001:db8::1".parse().unwrap();
+        let net = IpNetwork::with_netmask(addr, "ffff:ffff:ffff:ffff::".parse().unwrap()).unwrap();
+        assert_eq!(net.prefix_len(), 64);
+        assert_eq!(net.to_string(), "2001:db8::1/64");
+        assert!(IpNetwork::new(addr, 129).is_err());
+    }
+
+    #[test]
+    fn test_network() {
+        let n = net("10.0.0.2", 24);
+        assert_eq!(n.to_string(), "10.0.0.2/24");
+        assert_eq!(n.network(), "10.0.0.0".parse::<IpAddr>().unwrap());
+        assert_eq!(n.broadcast(), Some("10.0.0.255".parse().unwrap()));
+        assert!(n.contains("10.0.0.200".parse().unwrap()));
+        assert!(!n.contains("10.0.1.1".parse().unwrap()));
+        assert!(!n.contains("::a00:1".parse().unwrap()));
+        assert_eq!(
+            net("10.0.0.2", 0).network(),
+            "0.0.0.0".parse::<IpAddr>().unwrap()
+        );
+
+        let n = net("2001:db8::1", 64);
+        assert_eq!(n.network(), "2001:db8::".parse::<IpAddr>().unwrap());
+        assert_eq!(n.broadcast(), None);
+        assert!(n.contains("2001:db8::ffff".parse().unwrap()));
+        assert!(net("::1", 0).contains("2001:db8::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_hosts() {
+        let hosts: Vec<IpAddr> = net("192.168.1.1", 30).hosts().collect();
+        assert_eq!(
+            hosts,
+            vec![
+                "192.168.1.1".parse::<IpAddr>().unwrap(),
+                "192.168.1.2".parse().unwrap()
+            ]
+        );
+        assert_eq!(net("192.168.1.0", 31).hosts().count(), 2);
+        assert_eq!(net("192.168.1.1", 32).hosts().count(), 1);
+        assert_eq!(net("10.0.0.0", 16).hosts().count(), (1 << 16) - 2);
+        assert_eq!(net("2001:db8::", 126).hosts().count(), 4);
+        assert_eq!(
+            net("2001:db8::", 0).hosts().next(),
+            Some("::".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_address_network() {
+        let ifs = Interface::get_all().unwrap();
+        let lo = ifs.iter().find(|i| i.is_loopback()).unwrap();
+        let addr = lo.addresses.iter().find(|a| a.kind == Kind::Ipv4).unwrap();
+        assert_eq!(addr.network().unwrap().unwrap().to_string(), "127.0.0.1/8");
+        assert_eq!(addr.prefix_len().unwrap(), Some(8));
+    }
+}
@@ -0,0 +1,995 @@
+//! Routing tables.  Routes are read and changed over rtnetlink, in the namespace of the calling
+//! thread.  This is only available on Linux.
+
+use std::fmt;
+use std::net;
+
+use libc;
+
+use nix::errno::Errno;
+
+use netlink::{self, Message, Request, Socket};
+use {Interface, InterfacesError, Result};
+
+/// The id of the main routing table, which holds the routes that are usually meant by "the"
+/// routing table.
+pub const RT_TABLE_MAIN: u32 = 254;
+
+/// The id of the local routing table, which holds the routes for local and broadcast addresses.
+pub const RT_TABLE_LOCAL: u32 = 255;
+
+/// The id of the default routing table, which is consulted after the main one.
+pub const RT_TABLE_DEFAULT: u32 = 253;
+
+/// The length of `struct rtmsg`.
+const RTMSG_LEN: usize = 12;
+
+// Attributes of route messages.
+const RTA_DST: u16 = 1;
+const RTA_OIF: u16 = 4;
+const RTA_GATEWAY: u16 = 5;
+const RTA_PRIORITY: u16 = 6;
+const RTA_PREFSRC: u16 = 7;
+const RTA_METRICS: u16 = 8;
+const RTA_MULTIPATH: u16 = 9;
+const RTA_TABLE: u16 = 15;
+
+// Attributes inside `RTA_METRICS`.
+const RTAX_MTU: u16 = 2;
+
+/// The length of `struct rtnexthop`, which precedes the attributes of each path in
+/// `RTA_MULTIPATH`.
+const RTNEXTHOP_LEN: usize = 8;
+
+/// Set on routes that were cloned into the route cache, rather than configured.
+const RTM_F_CLONED: u32 = 0x200;
+
+const RTN_UNICAST: u8 = 1;
+const RTN_LOCAL: u8 = 2;
+const RTN_BROADCAST: u8 = 3;
+const RTN_ANYCAST: u8 = 4;
+const RTN_MULTICAST: u8 = 5;
+const RTN_BLACKHOLE: u8 = 6;
+const RTN_UNREACHABLE: u8 = 7;
+const RTN_PROHIBIT: u8 = 8;
+const RTN_THROW: u8 = 9;
+
+const RTPROT_REDIRECT: u8 = 1;
+const RTPROT_KERNEL: u8 = 2;
+const RTPROT_BOOT: u8 = 3;
+const RTPROT_STATIC: u8 = 4;
+const RTPROT_RA: u8 = 9;
+const RTPROT_DHCP: u8 = 16;
+
+const RT_SCOPE_UNIVERSE: u8 = 0;
+const RT_SCOPE_SITE: u8 = 200;
+const RT_SCOPE_LINK: u8 = 253;
+const RT_SCOPE_HOST: u8 = 254;
+const RT_SCOPE_NOWHERE: u8 = 255;
+
+/// The type of a route, which decides what happens to packets that match it.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RouteType {
+    /// Packets are forwarded to a gateway or directly to the destination.
+    Unicast,
+
+    /// The destination is an address of this host.
+    Local,
+
+    /// The destination is a broadcast address, and packets are sent as link-layer broadcasts.
+    Broadcast,
+
+    /// The destination is an anycast address of this host.
+    Anycast,
+
+    /// The destination is a multicast address.
+    Multicast,
+
+    /// Packets are silently dropped.
+    Blackhole,
+
+    /// Packets are dropped, and the sender is told that the destination is unreachable.
+    Unreachable,
+
+    /// Packets are dropped, and the sender is told that the destination is administratively
+    /// prohibited.
+    Prohibit,
+
+    /// The lookup in this table ends, and continues with the next routing rule.
+    Throw,
+
+    /// A type that is not known to this crate.  The interior value is the numerical type.
+    Other(u8),
+}
+
+impl RouteType {
+    fn to_raw(self) -> u8 {
+        match self {
+            RouteType::Unicast => RTN_UNICAST,
+            RouteType::Local => RTN_LOCAL,
+            RouteType::Broadcast => RTN_BROADCAST,
+            RouteType::Anycast => RTN_ANYCAST,
+            RouteType::Multicast => RTN_MULTICAST,
+            RouteType::Blackhole => RTN_BLACKHOLE,
+            RouteType::Unreachable => RTN_UNREACHABLE,
+            RouteType::Prohibit => RTN_PROHIBIT,
+            RouteType::Throw => RTN_THROW,
+            RouteType::Other(v) => v,
+        }
+    }
+
+    fn from_raw(v: u8) -> RouteType {
+        match v {
+            RTN_UNICAST => RouteType::Unicast,
+            RTN_LOCAL => RouteType::Local,
+            RTN_BROADCAST => RouteType::Broadcast,
+            RTN_ANYCAST => RouteType::Anycast,
+            RTN_MULTICAST => RouteType::Multicast,
+            RTN_BLACKHOLE => RouteType::Blackhole,
+            RTN_UNREACHABLE => RouteType::Unreachable,
+            RTN_PROHIBIT => RouteType::Prohibit,
+            RTN_THROW => RouteType::Throw,
+            v => RouteType::Other(v),
+        }
+    }
+}
+
+impl fmt::Display for RouteType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RouteType::Unicast => write!(f, "unicast"),
+            RouteType::Local => write!(f, "local"),
+            RouteType::Broadcast => write!(f, "broadcast"),
+            RouteType::Anycast => write!(f, "anycast"),
+            RouteType::Multicast => write!(f, "multicast"),
+            RouteType::Blackhole => write!(f, "blackhole"),
+            RouteType::Unreachable => write!(f, "unreachable"),
+            RouteType::Prohibit => write!(f, "prohibit"),
+            RouteType::Throw => write!(f, "throw"),
+            RouteType::Other(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// The origin of a route.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RouteProtocol {
+    /// The route was installed by an ICMP redirect.
+    Redirect,
+
+    /// The route was installed by the kernel, e.g. for the prefix of an address.
+    Kernel,
+
+    /// The route was installed during boot, or by a tool that did not specify a protocol.
+    Boot,
+
+    /// The route was installed by an administrator.
+    Static,
+
+    /// The route was learned from an IPv6 router advertisement.
+    Ra,
+
+    /// The route was installed by a DHCP client.
+    Dhcp,
+
+    /// Any other protocol, e.g. that of a routing daemon.  The interior value is the numerical
+    /// protocol, as listed in `/etc/iproute2/rt_protos`.
+    Other(u8),
+}
+
+impl RouteProtocol {
+    fn to_raw(self) -> u8 {
+        match self {
+            RouteProtocol::Redirect => RTPROT_REDIRECT,
+            RouteProtocol::Kernel => RTPROT_KERNEL,
+            RouteProtocol::Boot => RTPROT_BOOT,
+            RouteProtocol::Static => RTPROT_STATIC,
+            RouteProtocol::Ra => RTPROT_RA,
+            RouteProtocol::Dhcp => RTPROT_DHCP,
+            RouteProtocol::Other(v) => v,
+        }
+    }
+
+    fn from_raw(v: u8) -> RouteProtocol {
+        match v {
+            RTPROT_REDIRECT => RouteProtocol::Redirect,
+            RTPROT_KERNEL => RouteProtocol::Kernel,
+            RTPROT_BOOT => RouteProtocol::Boot,
+            RTPROT_STATIC => RouteProtocol::Static,
+            RTPROT_RA => RouteProtocol::Ra,
+            RTPROT_DHCP => RouteProtocol::Dhcp,
+            v => RouteProtocol::Other(v),
+        }
+    }
+}
+
+impl fmt::Display for RouteProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RouteProtocol::Redirect => write!(f, "redirect"),
+            RouteProtocol::Kernel => write!(f, "kernel"),
+            RouteProtocol::Boot => write!(f, "boot"),
+            RouteProtocol::Static => write!(f, "static"),
+            RouteProtocol::Ra => write!(f, "ra"),
+            RouteProtocol::Dhcp => write!(f, "dhcp"),
+            RouteProtocol::Other(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// The scope of a route, i.e. how far away its destination is.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RouteScope {
+    /// The destination may be anywhere, usually behind a gateway.
+    Universe,
+
+    /// The destination is within the local site (IPv6 only).
+    Site,
+
+    /// The destination is directly attached to the link.
+    Link,
+
+    /// The destination is this host.
+    Host,
+
+    /// The destination does not exist.
+    Nowhere,
+
+    /// A scope that is not known to this crate.  The interior value is the numerical scope.
+    Other(u8),
+}
+
+impl RouteScope {
+    fn to_raw(self) -> u8 {
+        match self {
+            RouteScope::Universe => RT_SCOPE_UNIVERSE,
+            RouteScope::Site => RT_SCOPE_SITE,
+            RouteScope::Link => RT_SCOPE_LINK,
+            RouteScope::Host => RT_SCOPE_HOST,
+            RouteScope::Nowhere => RT_SCOPE_NOWHERE,
+            RouteScope::Other(v) => v,
+        }
+    }
+
+    pub(crate) fn from_raw(v: u8) -> RouteScope {
+        match v {
+            RT_SCOPE_UNIVERSE => RouteScope::Universe,
+            RT_SCOPE_SITE => RouteScope::Site,
+            RT_SCOPE_LINK => RouteScope::Link,
+            RT_SCOPE_HOST => RouteScope::Host,
+            RT_SCOPE_NOWHERE => RouteScope::Nowhere,
+            v => RouteScope::Other(v),
+        }
+    }
+}
+
+impl fmt::Display for RouteScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RouteScope::Universe => write!(f, "global"),
+            RouteScope::Site => write!(f, "site"),
+            RouteScope::Link => write!(f, "link"),
+            RouteScope::Host => write!(f, "host"),
+            RouteScope::Nowhere => write!(f, "nowhere"),
+            RouteScope::Other(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// One of the paths of a multipath route.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct RouteNextHop {
+    /// The gateway that packets are forwarded to, if any.
+    pub gateway: Option<net::IpAddr>,
+
+    /// The index of the interface that packets are sent out of, if any.
+    pub ifindex: Option<u32>,
+
+    /// The relative weight of this path, from 1 to 256.  A path with weight 2 is chosen twice as
+    /// often as one with weight 1.
+    pub weight: u16,
+}
+
+impl RouteNextHop {
+    /// Returns a path through the given gateway, with a weight of 1.
+    pub fn new(gateway: net::IpAddr) -> RouteNextHop {
+        RouteNextHop {
+            gateway: Some(gateway),
+            ifindex: None,
+            weight: 1,
+        }
+    }
+
+    fn put(&self, req: &mut Request) {
+        let start = req.begin_struct();
+        req.put_u16(0);
+        req.put_u8(0);
+        req.put_u8((self.weight - 1) as u8);
+        req.put_u32(self.ifindex.unwrap_or(0));
+        if let Some(gateway) = self.gateway {
+            req.attr_ip(RTA_GATEWAY, gateway);
+        }
+        req.end_nested(start);
+    }
+
+    fn parse_multipath(mut data: &[u8]) -> Option<Vec<RouteNextHop>> {
+        let mut ret = vec![];
+
+        while data.len() >= RTNEXTHOP_LEN {
+            let len = netlink::parse_u16(data)? as usize;
+            if len < RTNEXTHOP_LEN || len > data.len() {
+                return None;
+            }
+
+            let mut hop = RouteNextHop {
+                gateway: None,
+                ifindex: netlink::parse_u32(&data[4..8]).filter(|&i| i != 0),
+                weight: data[3] as u16 + 1,
+            };
+            for (ty, val) in netlink::Attrs::new(&data[RTNEXTHOP_LEN..len]) {
+                if ty == RTA_GATEWAY {
+                    hop.gateway = netlink::parse_ip(val);
+                }
+            }
+            ret.push(hop);
+
+            // Each `rtnexthop` is padded to a multiple of 4 bytes.
+            data = &data[((len + 3) & !3).min(data.len())..];
+        }
+
+        Some(ret)
+    }
+}
+
+/// A single entry of a routing table.  Routes returned by `routes` can be passed to
+/// `delete_route` as they are, and new ones are usually built from `Route::new`:
+///
+/// ```no_run
+/// # use interfaces::route::{self, Route};
+/// let route = Route {
+///     gateway: Some("192.0.2.1".parse().unwrap()),
+///     metric: 100,
+///     ..Route::new("198.51.100.0".parse().unwrap(), 24)
+/// };
+/// route::add_route(&route).unwrap();
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Route {
+    /// The destination prefix.  This is the unspecified address of the route's family (e.g.
+    /// `0.0.0.0`) for default routes.
+    pub destination: net::IpAddr,
+
+    /// The length of the destination prefix, in bits.
+    pub prefix_len: u8,
+
+    /// The gateway that packets are forwarded to, if any.
+    pub gateway: Option<net::IpAddr>,
+
+    /// The index of the interface that packets are sent out of, if any.  See `Route::interface`.
+    pub ifindex: Option<u32>,
+
+    /// The paths of a multipath (ECMP) route.  This is empty for routes with a single path, which
+    /// use `gateway` and `ifindex` instead.
+    pub nexthops: Vec<RouteNextHop>,
+
+    /// The source address that is preferred for packets sent along this route, if any.  This
+    /// must be an address of this host.
+    pub source: Option<net::IpAddr>,
+
+    /// The metric (or priority) of the route.  Lower metrics are preferred.
+    pub metric: u32,
+
+    /// The path MTU to use for this route, if it differs from the MTU of the interface.
+    pub mtu: Option<u32>,
+
+    /// The id of the routing table this route is in, e.g. `RT_TABLE_MAIN`.
+    pub table: u32,
+
+    /// The origin of the route.
+    pub protocol: RouteProtocol,
+
+    /// The scope of the route.
+    pub scope: RouteScope,
+
+    /// The type of the route.
+    pub kind: RouteType,
+}
+
+impl Route {
+    /// Returns a unicast route to the given prefix in the main table, with neither a gateway nor
+    /// an interface.  This is the same default that `ip route add` uses, so the scope is
+    /// `Universe` and should be changed to `Link` for routes that only name an interface.
+    pub fn new(destination: net::IpAddr, prefix_len: u8) -> Route {
+        Route {
+            destination,
+            prefix_len,
+            gateway: None,
+            ifindex: None,
+            nexthops: vec![],
+            source: None,
+            metric: 0,
+            mtu: None,
+            table: RT_TABLE_MAIN,
+            protocol: RouteProtocol::Boot,
+            scope: RouteScope::Universe,
+            kind: RouteType::Unicast,
+        }
+    }
+
+    /// Returns whether this is a default route, i.e. whether it matches every destination.
+    pub fn is_default(&self) -> bool {
+        self.prefix_len == 0
+    }
+
+    /// Returns whether this is an IPv6 route.
+    pub fn is_ipv6(&self) -> bool {
+        self.destination.is_ipv6()
+    }
+
+    /// Returns the interface that packets are sent out of, if any.  The interface is looked up in
+    /// the namespace of the calling thread.
+    pub fn interface(&self) -> Result<Option<Interface>> {
+        match self.ifindex {
+            Some(index) => Interface::get_by_index(index),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns whether packets along this route may leave through the interface with the given
+    /// index, on any of its paths.
+    fn uses_interface(&self, index: u32) -> bool {
+        self.ifindex == Some(index) || self.nexthops.iter().any(|h| h.ifindex == Some(index))
+    }
+
+    /// Checks that the addresses of this route are all of the same family, and that the prefix
+    /// length and weights are in range.
+    fn validate(&self) -> Result<()> {
+        let max_len = if self.is_ipv6() { 128 } else { 32 };
+        let same_family = |ip: &net::IpAddr| ip.is_ipv6() == self.is_ipv6();
+
+        let valid = self.prefix_len <= max_len
+            && self.gateway.iter().all(same_family)
+            && self.source.iter().all(same_family)
+            && self
+                .nexthops
+                .iter()
+                .all(|h| h.gateway.iter().all(same_family) && h.weight >= 1 && h.weight <= 256);
+
+        if !valid {
+            return Err(InterfacesError::Errno(Errno::EINVAL));
+        }
+        Ok(())
+    }
+
+    /// Builds a request of the given type that describes this route.
+    fn request(&self, ty: u16, flags: u16) -> Result<Request> {
+        self.validate()?;
+
+        // Deletions match routes of any protocol and scope, as `ip route del` does.
+        let (protocol, scope) = if ty == netlink::RTM_DELROUTE {
+            (0, RT_SCOPE_NOWHERE)
+        } else {
+            (self.protocol.to_raw(), self.scope.to_raw())
+        };
+
+        let mut req = Request::new(ty, flags);
+        req.put_u8(if self.is_ipv6() {
+            libc::AF_INET6 as u8
+        } else {
+            libc::AF_INET as u8
+        });
+        req.put_u8(self.prefix_len);
+        req.put_u8(0);
+        req.put_u8(0);
+        // Tables above 255 only fit into `RTA_TABLE`.
+        req.put_u8(if self.table < 256 {
+            self.table as u8
+        } else {
+            0
+        });
+        req.put_u8(protocol);
+        req.put_u8(scope);
+        req.put_u8(self.kind.to_raw());
+        req.put_u32(0);
+
+        req.attr_u32(RTA_TABLE, self.table);
+        if !self.is_default() {
+            req.attr_ip(RTA_DST, self.destination);
+        }
+        if let Some(gateway) = self.gateway {
+            req.attr_ip(RTA_GATEWAY, gateway);
+        }
+        if let Some(index) = self.ifindex {
+            req.attr_u32(RTA_OIF, index);
+        }
+        if let Some(source) = self.source {
+            req.attr_ip(RTA_PREFSRC, source);
+        }
+        if self.metric != 0 {
+            req.attr_u32(RTA_PRIORITY, self.metric);
+        }
+        if let Some(mtu) = self.mtu {
+            let metrics = req.begin_nested(RTA_METRICS);
+            req.attr_u32(RTAX_MTU, mtu);
+            req.end_nested(metrics);
+        }
+        if !self.nexthops.is_empty() {
+            let multipath = req.begin_nested(RTA_MULTIPATH);
+            for hop in &self.nexthops {
+                hop.put(&mut req);
+            }
+            req.end_nested(multipath);
+        }
+
+        Ok(req)
+    }
+
+    fn from_message(msg: &Message) -> Option<Route> {
+        if msg.payload.len() < RTMSG_LEN {
+            return None;
+        }
+
+        let hdr = &msg.payload;
+        let destination = match hdr[0] as i32 {
+            libc::AF_INET => net::IpAddr::V4(net::Ipv4Addr::UNSPECIFIED),
+            libc::AF_INET6 => net::IpAddr::V6(net::Ipv6Addr::UNSPECIFIED),
+            _ => return None,
+        };
+
+        let mut ret = Route {
+            prefix_len: hdr[1],
+            table: hdr[4] as u32,
+            protocol: RouteProtocol::from_raw(hdr[5]),
+            scope: RouteScope::from_raw(hdr[6]),
+            kind: RouteType::from_raw(hdr[7]),
+            ..Route::new(destination, 0)
+        };
+
+        for (ty, val) in msg.attrs(RTMSG_LEN) {
+            match ty {
+                RTA_DST => ret.destination = netlink::parse_ip(val)?,
+                RTA_GATEWAY => ret.gateway = netlink::parse_ip(val),
+                RTA_OIF => ret.ifindex = netlink::parse_u32(val),
+                RTA_PREFSRC => ret.source = netlink::parse_ip(val),
+                RTA_PRIORITY => ret.metric = netlink::parse_u32(val)?,
+                RTA_TABLE => ret.table = netlink::parse_u32(val)?,
+                RTA_MULTIPATH => ret.nexthops = RouteNextHop::parse_multipath(val)?,
+                RTA_METRICS => {
+                    ret.mtu = netlink::Attrs::new(val)
+                        .filter(|&(ty, _)| ty == RTAX_MTU)
+                        .filter_map(|(_, val)| netlink::parse_u32(val))
+                        .next()
+                }
+                _ => {}
+            }
+        }
+
+        Some(ret)
+    }
+}
+
+/// Returns the routes of all routing tables, for both IPv4 and IPv6.
+pub fn routes() -> Result<Vec<Route>> {
+    dump_routes(&mut Socket::route()?)
+}
+
+/// Returns the default route of the main routing table, if there is one.  If there are several,
+/// IPv4 routes are preferred over IPv6 ones, and then those with the lowest metric.
+pub fn default_gateway() -> Result<Option<Route>> {
+    let mut routes: Vec<Route> = routes()?
+        .into_iter()
+        .filter(|r| r.is_default() && r.table == RT_TABLE_MAIN && r.kind == RouteType::Unicast)
+        .collect();
+    routes.sort_by_key(|r| (r.is_ipv6(), r.metric));
+    Ok(routes.into_iter().next())
+}
+
+/// Adds a new route.  Fails with `EEXIST` if an identical route already exists.
+pub fn add_route(route: &Route) -> Result<()> {
+    let flags = netlink::NLM_F_CREATE | netlink::NLM_F_EXCL;
+    Socket::route()?.request(route.request(netlink::RTM_NEWROUTE, flags)?)
+}
+
+/// Adds a new route, or replaces the existing one to the same destination (with the same table,
+/// metric and type).
+pub fn replace_route(route: &Route) -> Result<()> {
+    let flags = netlink::NLM_F_CREATE | netlink::NLM_F_REPLACE;
+    Socket::route()?.request(route.request(netlink::RTM_NEWROUTE, flags)?)
+}
+
+/// Deletes a route.  Only the destination, table, metric and type have to match, but any
+/// gateway, interface or MTU that is given has to match as well.
+pub fn delete_route(route: &Route) -> Result<()> {
+    Socket::route()?.request(route.request(netlink::RTM_DELROUTE, 0)?)
+}
+
+/// How the kernel would send packets to a destination, as returned by `route_lookup`.
+#[derive(Debug)]
+pub struct RouteLookup {
+    /// The interface that packets would be sent out of, if it could be found.  This is the
+    /// loopback interface for addresses of this host.
+    pub interface: Option<Interface>,
+
+    /// The gateway that packets would be forwarded to, or `None` if the destination is directly
+    /// reachable.
+    pub gateway: Option<net::IpAddr>,
+
+    /// The source address that the kernel would pick for packets to the destination, if any.
+    pub source: Option<net::IpAddr>,
+
+    /// The type of the route that matched, e.g. `Local` for addresses of this host.
+    pub kind: RouteType,
+}
+
+/// Asks the kernel which route it would use to reach `dest`, like `ip route get`.  This answers
+/// "which interface and source address would be used?" without guessing from the addresses of
+/// every interface.
+///
+/// The lookup is done over rtnetlink.  If netlink sockets cannot be opened (e.g. in a sandbox),
+/// a connected UDP socket is used instead.  In that case the gateway is never known, and the
+/// interface is only found if it has the chosen source address.
+///
+/// Destinations that cannot be reached fail with `ENETUNREACH` (or the error of the matching
+/// unreachable or prohibit route).
+pub fn route_lookup(dest: net::IpAddr) -> Result<RouteLookup> {
+    match Socket::route() {
+        Ok(mut sock) => lookup_netlink(&mut sock, dest),
+        Err(InterfacesError::Errno(Errno::EPROTONOSUPPORT))
+        | Err(InterfacesError::Errno(Errno::EAFNOSUPPORT))
+        | Err(InterfacesError::Errno(Errno::EACCES))
+        | Err(InterfacesError::Errno(Errno::EPERM)) => lookup_udp(dest),
+        Err(e) => Err(e),
+    }
+}
+
+impl Interface {
+    /// Returns the routes that send packets out of this interface, from all routing tables.
+    pub fn routes(&self) -> Result<Vec<Route>> {
+        let index = self.index()?;
+        let mut sock = self.netlink_socket(netlink::NETLINK_ROUTE)?;
+        Ok(dump_routes(&mut sock)?
+            .into_iter()
+            .filter(|r| r.uses_interface(index))
+            .collect())
+    }
+
+    /// Adds a route through this interface, i.e. `route` with its `ifindex` set to this one.
+    pub fn add_route(&self, route: &Route) -> Result<()> {
+        let flags = netlink::NLM_F_CREATE | netlink::NLM_F_EXCL;
+        self.route_request(route, netlink::RTM_NEWROUTE, flags)
+    }
+
+    /// Adds or replaces a route through this interface, like `replace_route`.
+    pub fn replace_route(&self, route: &Route) -> Result<()> {
+        let flags = netlink::NLM_F_CREATE | netlink::NLM_F_REPLACE;
+        self.route_request(route, netlink::RTM_NEWROUTE, flags)
+    }
+
+    /// Deletes a route through this interface, like `delete_route`.
+    pub fn delete_route(&self, route: &Route) -> Result<()> {
+        self.route_request(route, netlink::RTM_DELROUTE, 0)
+    }
+
+    fn route_request(&self, route: &Route, ty: u16, flags: u16) -> Result<()> {
+        let route = Route {
+            ifindex: Some(self.index()?),
+            ..route.clone()
+        };
+        let req = route.request(ty, flags)?;
+        self.netlink_socket(netlink::NETLINK_ROUTE)?.request(req)
+    }
+}
+
+fn dump_routes(sock: &mut Socket) -> Result<Vec<Route>> {
+    // An all-zero `rtmsg` selects routes of every family and table.
+    let mut req = Request::new(netlink::RTM_GETROUTE, 0);
+    for _ in 0..RTMSG_LEN / 4 {
+        req.put_u32(0);
+    }
+
+    let msgs = sock.dump(req)?;
+    Ok(msgs
+        .iter()
+        .filter(|msg| msg.ty == netlink::RTM_NEWROUTE && !is_cloned(msg))
+        .filter_map(Route::from_message)
+        .collect())
+}
+
+/// Returns whether the message describes a route that was cloned into the route cache, rather
+/// than configured.
+fn is_cloned(msg: &Message) -> bool {
+    msg.payload.len() >= RTMSG_LEN
+        && netlink::parse_u32(&msg.payload[8..12]).unwrap() & RTM_F_CLONED != 0
+}
+
+/// Asks the kernel for the route it would use to reach `dest`.
+fn lookup_netlink(sock: &mut Socket, dest: net::IpAddr) -> Result<RouteLookup> {
+    let (family, len) = match dest {
+        net::IpAddr::V4(..) => (libc::AF_INET as u8, 32),
+        net::IpAddr::V6(..) => (libc::AF_INET6 as u8, 128),
+    };
+
+    let mut req = Request::new(netlink::RTM_GETROUTE, 0);
+    req.put_u8(family);
+    req.put_u8(len);
+    for _ in 2..RTMSG_LEN {
+        req.put_u8(0);
+    }
+    req.attr_ip(RTA_DST, dest);
+
+    let route = sock
+        .transact(req)?
+        .iter()
+        .filter(|msg| msg.ty == netlink::RTM_NEWROUTE)
+        .filter_map(Route::from_message)
+        .next()
+        .ok_or(InterfacesError::Errno(Errno::ENETUNREACH))?;
+
+    Ok(RouteLookup {
+        interface: route.interface()?,
+        gateway: route.gateway,
+        source: route.source,
+        kind: route.kind,
+    })
+}
+
+/// Finds the source address for `dest` by connecting a UDP socket to it, which performs a route
+/// lookup without sending anything.  The interface is then found by its address.
+fn lookup_udp(dest: net::IpAddr) -> Result<RouteLookup> {
+    let any: net::IpAddr = match dest {
+        net::IpAddr::V4(..) => net::Ipv4Addr::UNSPECIFIED.into(),
+        net::IpAddr::V6(..) => net::Ipv6Addr::UNSPECIFIED.into(),
+    };
+
+    let sock = net::UdpSocket::bind((any, 0))?;
+    sock.connect((dest, 9))?;
+    let source = sock.local_addr()?.ip();
+
+    let interface = Interface::get_all()?
+        .into_iter()
+        .find(|iface| iface.addresses.iter().any(|addr| addr.ip == Some(source)));
+
+    Ok(RouteLookup {
+        interface,
+        gateway: None,
+        source: Some(source),
+        kind: if source == dest {
+            RouteType::Local
+        } else {
+            RouteType::Unicast
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutil;
+
+    fn to_message(route: &Route) -> Message {
+        let req = route.request(netlink::RTM_NEWROUTE, 0).unwrap();
+        Message {
+            ty: netlink::RTM_NEWROUTE,
+            payload: req.body().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_route_roundtrip() {
+        let routes = vec![
+            Route {
+                gateway: Some("10.1.2.1".parse().unwrap()),
+                ifindex: Some(3),
+                source: Some("10.1.2.5".parse().unwrap()),
+                metric: 100,
+                mtu: Some(1400),
+                table: 1000,
+                protocol: RouteProtocol::Static,
+                ..Route::new("10.1.2.0".parse().unwrap(), 24)
+            },
+            Route {
+                nexthops: vec![
+                    RouteNextHop::new("fd00::1".parse().unwrap()),
+                    RouteNextHop {
+                        ifindex: Some(2),
+                        weight: 256,
+                        ..RouteNextHop::new("fd00::2".parse().unwrap())
+                    },
+                ],
+                ..Route::new("::".parse().unwrap(), 0)
+            },
+            Route {
+                kind: RouteType::Blackhole,
+                ..Route::new("192.0.2.0".parse().unwrap(), 24)
+            },
+        ];
+
+        for route in routes {
+            assert_eq!(Route::from_message(&to_message(&route)), Some(route));
+        }
+    }
+
+    #[test]
+    fn test_route_validate() {
+        let route = Route::new("10.0.0.0".parse().unwrap(), 8);
+        assert!(route.validate().is_ok());
+
+        let invalid = vec![
+            Route {
+                prefix_len: 33,
+                ..route.clone()
+            },
+            Route {
+                gateway: Some("fd00::1".parse().unwrap()),
+                ..route.clone()
+            },
+            Route {
+                nexthops: vec![RouteNextHop {
+                    weight: 0,
+                    ..RouteNextHop::new("10.0.0.1".parse().unwrap())
+                }],
+                ..route.clone()
+            },
+        ];
+        for route in invalid {
+            assert!(route.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_loopback_routes() {
+        testutil::in_netns(|| {
+            let mut lo = Interface::get_by_name("lo").unwrap().unwrap();
+            assert!(lo.routes().unwrap().is_empty());
+            lo.set_up(true).unwrap();
+
+            let routes = lo.routes().unwrap();
+            let local = routes
+                .iter()
+                .find(|r| r.destination == net::IpAddr::from([127, 0, 0, 0]) && r.prefix_len == 8)
+                .unwrap();
+            assert_eq!(local.table, RT_TABLE_LOCAL);
+            assert_eq!(local.kind, RouteType::Local);
+            assert_eq!(local.protocol, RouteProtocol::Kernel);
+            assert_eq!(local.scope, RouteScope::Host);
+            assert_eq!(local.interface().unwrap(), Some(lo));
+
+            assert!(default_gateway().unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_route_lookup() {
+        testutil::in_netns(|| {
+            let localhost: net::IpAddr = "127.0.0.1".parse().unwrap();
+            let mut lo = Interface::get_by_name("lo").unwrap().unwrap();
+            lo.set_up(true).unwrap();
+
+            let found = route_lookup(localhost).unwrap();
+            assert_eq!(found.kind, RouteType::Local);
+            assert_eq!(found.source, Some(localhost));
+            assert_eq!(found.gateway, None);
+            assert_eq!(found.interface.as_ref(), Some(&lo));
+
+            let found = lookup_udp(localhost).unwrap();
+            assert_eq!(found.kind, RouteType::Local);
+            assert_eq!(found.source, Some(localhost));
+            assert_eq!(found.interface, Some(lo));
+
+            let (mut veth, mut peer) = Interface::create_veth("veth0", "veth1").unwrap();
+            veth.set_up(true).unwrap();
+            peer.set_up(true).unwrap();
+            veth.add_route(&Route {
+                scope: RouteScope::Link,
+                ..Route::new("10.9.0.0".parse().unwrap(), 24)
+            })
+            .unwrap();
+            add_route(&Route {
+                gateway: Some("10.9.0.1".parse().unwrap()),
+                ..Route::new("10.10.0.0".parse().unwrap(), 16)
+            })
+            .unwrap();
+
+            let found = route_lookup("10.10.1.1".parse().unwrap()).unwrap();
+            assert_eq!(found.kind, RouteType::Unicast);
+            assert_eq!(found.gateway, Some("10.9.0.1".parse().unwrap()));
+            assert_eq!(found.interface, Some(veth));
+
+            match route_lookup("192.0.2.1".parse().unwrap()) {
+                Err(InterfacesError::Errno(Errno::ENETUNREACH)) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+        });
+    }
+
+    #[test]
+    fn test_change_routes() {
+        testutil::in_netns(|| {
+            let (mut veth, mut peer) = Interface::create_veth("veth0", "veth1").unwrap();
+            veth.set_up(true).unwrap();
+            peer.set_up(true).unwrap();
+
+            let find = |dest: &str| {
+                let dest: net::IpAddr = dest.parse().unwrap();
+                routes()
+                    .unwrap()
+                    .into_iter()
+                    .find(|r| r.destination == dest)
+            };
+
+            // A route through the interface only.
+            let link = Route {
+                scope: RouteScope::Link,
+                metric: 10,
+                mtu: Some(1400),
+                ..Route::new("10.9.0.0".parse().unwrap(), 24)
+            };
+            veth.add_route(&link).unwrap();
+            let found = find("10.9.0.0").unwrap();
+            assert_eq!(found.ifindex, Some(veth.index().unwrap()));
+            assert_eq!(found.mtu, Some(1400));
+            assert_eq!(found.protocol, RouteProtocol::Boot);
+            assert!(veth.routes().unwrap().contains(&found));
+
+            match veth.add_route(&link) {
+                Err(InterfacesError::Errno(Errno::EEXIST)) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+            let link = Route {
+                mtu: Some(1300),
+                ..link
+            };
+            veth.replace_route(&link).unwrap();
+            assert_eq!(find("10.9.0.0").unwrap().mtu, Some(1300));
+
+            // A route through a gateway on that link, in another table.
+            let via = Route {
+                gateway: Some("10.9.0.1".parse().unwrap()),
+                table: 1000,
+                ..Route::new("10.10.0.0".parse().unwrap(), 16)
+            };
+            add_route(&via).unwrap();
+            let found = find("10.10.0.0").unwrap();
+            assert_eq!(found.table, 1000);
+            assert_eq!(found.ifindex, Some(veth.index().unwrap()));
+
+            // A multipath route over two gateways.
+            let ecmp = Route {
+                nexthops: vec![
+                    RouteNextHop::new("10.9.0.1".parse().unwrap()),
+                    RouteNextHop {
+                        weight: 3,
+                        ..RouteNextHop::new("10.9.0.2".parse().unwrap())
+                    },
+                ],
+                ..Route::new("10.11.0.0".parse().unwrap(), 16)
+            };
+            add_route(&ecmp).unwrap();
+            let found = find("10.11.0.0").unwrap();
+            let weights: Vec<u16> = found.nexthops.iter().map(|h| h.weight).collect();
+            assert_eq!(weights, vec![1, 3]);
+
+            // Routes that drop packets.
+            for &(dest, len, kind) in &[
+                ("192.0.2.0", 24, RouteType::Blackhole),
+                ("198.51.100.0", 24, RouteType::Unreachable),
+                ("2001:db8::", 32, RouteType::Prohibit),
+            ] {
+                let route = Route {
+                    kind,
+                    ..Route::new(dest.parse().unwrap(), len)
+                };
+                add_route(&route).unwrap();
+                assert_eq!(find(dest).unwrap().kind, kind);
+                delete_route(&route).unwrap();
+                assert!(find(dest).is_none());
+            }
+
+            delete_route(&found).unwrap();
+            delete_route(&via).unwrap();
+            veth.delete_route(&link).unwrap();
+            // Only the IPv6 link-local routes that the kernel added are left.
+            assert!(veth.routes().unwrap().iter().all(|r| r.is_ipv6()));
+        });
+    }
+}
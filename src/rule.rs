@@ -0,0 +1,438 @@
+//! Policy routing rules, which decide which routing table is consulted for a packet.  Rules are
+//! read and changed over rtnetlink, in the namespace of the calling thread.  This is only
+//! available on Linux.
+
+use std::fmt;
+use std::net;
+
+use libc;
+use nix::errno::Errno;
+
+use netlink::{self, Message, Request, Socket};
+use {InterfacesError, Kind, Result};
+
+/// The length of `struct fib_rule_hdr`.
+const FIB_RULE_HDR_LEN: usize = 12;
+
+// Attributes of rule messages.
+const FRA_DST: u16 = 1;
+const FRA_SRC: u16 = 2;
+const FRA_IIFNAME: u16 = 3;
+const FRA_GOTO: u16 = 4;
+const FRA_PRIORITY: u16 = 6;
+const FRA_FWMARK: u16 = 10;
+const FRA_TABLE: u16 = 15;
+const FRA_FWMASK: u16 = 16;
+const FRA_OIFNAME: u16 = 17;
+
+const FR_ACT_TO_TBL: u8 = 1;
+const FR_ACT_GOTO: u8 = 2;
+const FR_ACT_NOP: u8 = 3;
+const FR_ACT_BLACKHOLE: u8 = 6;
+const FR_ACT_UNREACHABLE: u8 = 7;
+const FR_ACT_PROHIBIT: u8 = 8;
+
+/// Set on rules whose selector is inverted (`ip rule add not ...`).
+const FIB_RULE_INVERT: u32 = 0x2;
+
+/// What happens to packets that match a rule.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RuleAction {
+    /// The routing table with the given id is consulted, e.g. `route::RT_TABLE_MAIN`.
+    Lookup(u32),
+
+    /// Processing continues with the rule that has the given priority.
+    Goto(u32),
+
+    /// Nothing happens, and processing continues with the next rule.
+    Nop,
+
+    /// Packets are silently dropped.
+    Blackhole,
+
+    /// Packets are dropped, and the sender is told that the destination is unreachable.
+    Unreachable,
+
+    /// Packets are dropped, and the sender is told that the destination is administratively
+    /// prohibited.
+    Prohibit,
+
+    /// An action that is not known to this crate.  The interior value is the numerical action.
+    Other(u8),
+}
+
+impl RuleAction {
+    fn to_raw(self) -> u8 {
+        match self {
+            RuleAction::Lookup(..) => FR_ACT_TO_TBL,
+            RuleAction::Goto(..) => FR_ACT_GOTO,
+            RuleAction::Nop => FR_ACT_NOP,
+            RuleAction::Blackhole => FR_ACT_BLACKHOLE,
+            RuleAction::Unreachable => FR_ACT_UNREACHABLE,
+            RuleAction::Prohibit => FR_ACT_PROHIBIT,
+            RuleAction::Other(v) => v,
+        }
+    }
+}
+
+impl fmt::Display for RuleAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RuleAction::Lookup(table) => write!(f, "lookup {}", table),
+            RuleAction::Goto(target) => write!(f, "goto {}", target),
+            RuleAction::Nop => write!(f, "nop"),
+            RuleAction::Blackhole => write!(f, "blackhole"),
+            RuleAction::Unreachable => write!(f, "unreachable"),
+            RuleAction::Prohibit => write!(f, "prohibit"),
+            RuleAction::Other(v) => write!(f, "action {}", v),
+        }
+    }
+}
+
+/// A single policy routing rule.  A packet matches a rule if it matches every selector that is
+/// set; rules are tried in order of increasing priority.  New rules are usually built from
+/// `Rule::new`:
+///
+/// ```no_run
+/// # use interfaces::Kind;
+/// # use interfaces::rule::{self, Rule, RuleAction};
+/// let rule = Rule {
+///     source: Some("192.0.2.0".parse().unwrap()),
+///     source_len: 24,
+///     priority: Some(1000),
+///     ..Rule::new(Kind::Ipv4, RuleAction::Lookup(100))
+/// };
+/// rule::add_rule(&rule).unwrap();
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Rule {
+    /// The address family of the rule, either `Kind::Ipv4` or `Kind::Ipv6`.
+    pub family: Kind,
+
+    /// The prefix that the source address has to be in, if any.
+    pub source: Option<net::IpAddr>,
+
+    /// The length of the source prefix, in bits.
+    pub source_len: u8,
+
+    /// The prefix that the destination address has to be in, if any.
+    pub destination: Option<net::IpAddr>,
+
+    /// The length of the destination prefix, in bits.
+    pub destination_len: u8,
+
+    /// The name of the interface that the packet has to arrive on, if any.  The loopback
+    /// interface matches locally generated packets.
+    pub iif: Option<String>,
+
+    /// The name of the interface that the packet has to be sent from, if any.  This only applies
+    /// to locally generated packets from sockets bound to that interface.
+    pub oif: Option<String>,
+
+    /// The firewall mark that the packet has to carry, if any.
+    pub fwmark: Option<u32>,
+
+    /// The mask that is applied to the firewall mark before comparing it, if any.
+    pub fwmask: Option<u32>,
+
+    /// The priority of the rule.  If this is `None` when adding a rule, the kernel picks one just
+    /// below the first existing rule.
+    pub priority: Option<u32>,
+
+    /// Whether the selectors are inverted, i.e. whether the rule applies to the packets that do
+    /// not match them.
+    pub invert: bool,
+
+    /// What happens to packets that match.
+    pub action: RuleAction,
+}
+
+impl Rule {
+    /// Returns a rule of the given family that matches every packet.
+    pub fn new(family: Kind, action: RuleAction) -> Rule {
+        Rule {
+            family,
+            source: None,
+            source_len: 0,
+            destination: None,
+            destination_len: 0,
+            iif: None,
+            oif: None,
+            fwmark: None,
+            fwmask: None,
+            priority: None,
+            invert: false,
+            action,
+        }
+    }
+
+    /// Checks that the family is IPv4 or IPv6, and that the prefixes belong to it.
+    fn validate(&self) -> Result<()> {
+        let (ipv6, max_len) = match self.family {
+            Kind::Ipv4 => (false, 32),
+            Kind::Ipv6 => (true, 128),
+            _ => return Err(InterfacesError::Errno(Errno::EAFNOSUPPORT)),
+        };
+        let valid_prefix = |ip: Option<net::IpAddr>, len| {
+            ip.map_or(len == 0, |ip| ip.is_ipv6() == ipv6 && len <= max_len)
+        };
+
+        if !valid_prefix(self.source, self.source_len)
+            || !valid_prefix(self.destination, self.destination_len)
+        {
+            return Err(InterfacesError::Errno(Errno::EINVAL));
+        }
+        Ok(())
+    }
+
+    /// Builds a request of the given type that describes this rule.
+    fn request(&self, ty: u16, flags: u16) -> Result<Request> {
+        self.validate()?;
+
+        let family = match self.family {
+            Kind::Ipv6 => libc::AF_INET6,
+            _ => libc::AF_INET,
+        };
+        let table = match self.action {
+            RuleAction::Lookup(table) => table,
+            _ => 0,
+        };
+
+        let mut req = Request::new(ty, flags);
+        req.put_u8(family as u8);
+        req.put_u8(self.destination_len);
+        req.put_u8(self.source_len);
+        req.put_u8(0);
+        // Tables above 255 only fit into `FRA_TABLE`.
+        req.put_u8(if table < 256 { table as u8 } else { 0 });
+        req.put_u8(0);
+        req.put_u8(0);
+        req.put_u8(self.action.to_raw());
+        req.put_u32(if self.invert { FIB_RULE_INVERT } else { 0 });
+
+        if let Some(source) = self.source {
+            req.attr_ip(FRA_SRC, source);
+        }
+        if let Some(destination) = self.destination {
+            req.attr_ip(FRA_DST, destination);
+        }
+        if let Some(ref iif) = self.iif {
+            req.attr_str(FRA_IIFNAME, iif);
+        }
+        if let Some(ref oif) = self.oif {
+            req.attr_str(FRA_OIFNAME, oif);
+        }
+        if let Some(fwmark) = self.fwmark {
+            req.attr_u32(FRA_FWMARK, fwmark);
+        }
+        if let Some(fwmask) = self.fwmask {
+            req.attr_u32(FRA_FWMASK, fwmask);
+        }
+        if let Some(priority) = self.priority {
+            req.attr_u32(FRA_PRIORITY, priority);
+        }
+        match self.action {
+            RuleAction::Lookup(table) => req.attr_u32(FRA_TABLE, table),
+            RuleAction::Goto(target) => req.attr_u32(FRA_GOTO, target),
+            _ => {}
+        }
+
+        Ok(req)
+    }
+
+    fn from_message(msg: &Message) -> Option<Rule> {
+        if msg.payload.len() < FIB_RULE_HDR_LEN {
+            return None;
+        }
+
+        let hdr = &msg.payload;
+        let family = match hdr[0] as i32 {
+            libc::AF_INET => Kind::Ipv4,
+            libc::AF_INET6 => Kind::Ipv6,
+            _ => return None,
+        };
+
+        let mut table = hdr[4] as u32;
+        let mut target = 0;
+        let mut ret = Rule {
+            destination_len: hdr[1],
+            source_len: hdr[2],
+            invert: netlink::parse_u32(&hdr[8..12])? & FIB_RULE_INVERT != 0,
+            ..Rule::new(family, RuleAction::Other(hdr[7]))
+        };
+
+        for (ty, val) in msg.attrs(FIB_RULE_HDR_LEN) {
+            match ty {
+                FRA_SRC => ret.source = netlink::parse_ip(val),
+                FRA_DST => ret.destination = netlink::parse_ip(val),
+                FRA_IIFNAME => ret.iif = netlink::parse_str(val),
+                FRA_OIFNAME => ret.oif = netlink::parse_str(val),
+                FRA_FWMARK => ret.fwmark = netlink::parse_u32(val),
+                FRA_FWMASK => ret.fwmask = netlink::parse_u32(val),
+                FRA_PRIORITY => ret.priority = netlink::parse_u32(val),
+                FRA_TABLE => table = netlink::parse_u32(val)?,
+                FRA_GOTO => target = netlink::parse_u32(val)?,
+                _ => {}
+            }
+        }
+
+        ret.action = match hdr[7] {
+            FR_ACT_TO_TBL => RuleAction::Lookup(table),
+            FR_ACT_GOTO => RuleAction::Goto(target),
+            FR_ACT_NOP => RuleAction::Nop,
+            FR_ACT_BLACKHOLE => RuleAction::Blackhole,
+            FR_ACT_UNREACHABLE => RuleAction::Unreachable,
+            FR_ACT_PROHIBIT => RuleAction::Prohibit,
+            v => RuleAction::Other(v),
+        };
+
+        Some(ret)
+    }
+}
+
+/// Returns the rules of both families, in the order in which they are tried.
+pub fn rules() -> Result<Vec<Rule>> {
+    let mut req = Request::new(netlink::RTM_GETRULE, 0);
+    for _ in 0..FIB_RULE_HDR_LEN / 4 {
+        req.put_u32(0);
+    }
+
+    let msgs = Socket::route()?.dump(req)?;
+    let mut ret: Vec<Rule> = msgs
+        .iter()
+        .filter(|msg| msg.ty == netlink::RTM_NEWRULE)
+        .filter_map(Rule::from_message)
+        .collect();
+    ret.sort_by_key(|rule| rule.priority.unwrap_or(0));
+    Ok(ret)
+}
+
+/// Adds a new rule.  Fails with `EEXIST` if an identical rule already exists.
+pub fn add_rule(rule: &Rule) -> Result<()> {
+    let flags = netlink::NLM_F_CREATE | netlink::NLM_F_EXCL;
+    Socket::route()?.request(rule.request(netlink::RTM_NEWRULE, flags)?)
+}
+
+/// Deletes the first rule that matches every selector that is set in `rule`, as well as its
+/// action.
+pub fn delete_rule(rule: &Rule) -> Result<()> {
+    Socket::route()?.request(rule.request(netlink::RTM_DELRULE, 0)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use route::RT_TABLE_MAIN;
+    use testutil;
+
+    #[test]
+    fn test_rule_roundtrip() {
+        let rules = vec![
+            Rule {
+                source: Some("10.0.0.0".parse().unwrap()),
+                source_len: 8,
+                iif: Some("eth0".to_string()),
+                fwmark: Some(0x10),
+                fwmask: Some(0xff),
+                priority: Some(100),
+                ..Rule::new(Kind::Ipv4, RuleAction::Lookup(1000))
+            },
+            Rule {
+                destination: Some("2001:db8::".parse().unwrap()),
+                destination_len: 32,
+                oif: Some("wg0".to_string()),
+                invert: true,
+                ..Rule::new(Kind::Ipv6, RuleAction::Goto(200))
+            },
+            Rule::new(Kind::Ipv4, RuleAction::Prohibit),
+        ];
+
+        for rule in rules {
+            let req = rule.request(netlink::RTM_NEWRULE, 0).unwrap();
+            let msg = Message {
+                ty: netlink::RTM_NEWRULE,
+                payload: req.body().to_vec(),
+            };
+            assert_eq!(Rule::from_message(&msg), Some(rule));
+        }
+    }
+
+    #[test]
+    fn test_rule_validate() {
+        let rule = Rule::new(Kind::Ipv4, RuleAction::Nop);
+        assert!(rule.validate().is_ok());
+        assert!(Rule::new(Kind::Link, RuleAction::Nop).validate().is_err());
+        assert!(Rule {
+            source: Some("fd00::".parse().unwrap()),
+            source_len: 8,
+            ..rule.clone()
+        }
+        .validate()
+        .is_err());
+        assert!(Rule {
+            destination: Some("10.0.0.0".parse().unwrap()),
+            destination_len: 33,
+            ..rule.clone()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_change_rules() {
+        testutil::in_netns(|| {
+            // Every namespace starts out with the rule that consults the main table.
+            let main = rules()
+                .unwrap()
+                .into_iter()
+                .find(|r| r.family == Kind::Ipv4 && r.priority == Some(32766))
+                .unwrap();
+            assert_eq!(main.action, RuleAction::Lookup(RT_TABLE_MAIN));
+
+            let added = vec![
+                Rule {
+                    source: Some("192.0.2.0".parse().unwrap()),
+                    source_len: 24,
+                    priority: Some(1000),
+                    ..Rule::new(Kind::Ipv4, RuleAction::Lookup(100))
+                },
+                Rule {
+                    iif: Some("lo".to_string()),
+                    fwmark: Some(0x10),
+                    fwmask: Some(0xff),
+                    priority: Some(1001),
+                    ..Rule::new(Kind::Ipv4, RuleAction::Goto(2000))
+                },
+                Rule {
+                    priority: Some(2000),
+                    ..Rule::new(Kind::Ipv4, RuleAction::Unreachable)
+                },
+                Rule {
+                    destination: Some("2001:db8::".parse().unwrap()),
+                    destination_len: 32,
+                    priority: Some(1000),
+                    ..Rule::new(Kind::Ipv6, RuleAction::Lookup(100000))
+                },
+            ];
+
+            for rule in &added {
+                add_rule(rule).unwrap();
+            }
+            match add_rule(&added[0]) {
+                Err(InterfacesError::Errno(Errno::EEXIST)) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+
+            let found = rules().unwrap();
+            for rule in &added {
+                assert!(found.contains(rule), "{:?} not in {:?}", rule, found);
+            }
+
+            for rule in &added {
+                delete_rule(rule).unwrap();
+            }
+            let found = rules().unwrap();
+            assert!(added.iter().all(|rule| !found.contains(rule)));
+        });
+    }
+}
@@ -0,0 +1,50 @@
+//! Helpers for reading and writing the per-interface settings under `/proc/sys/net`.  The files
+//! there always refer to the namespace of the calling thread.
+
+use std::fs;
+use std::path::PathBuf;
+
+use nix::errno::Errno;
+
+use {InterfacesError, Result};
+
+/// Returns the path of the setting `key` of the interface `name`, for the given protocol
+/// (`"ipv4"` or `"ipv6"`).
+pub fn conf_path(proto: &str, name: &str, key: &str) -> PathBuf {
+    // Only the key notation of sysctl(8) turns dots into slashes; the directory of an interface
+    // like `eth0.100` keeps its name as it is.
+    PathBuf::from(format!("/proc/sys/net/{}/conf/{}/{}", proto, name, key))
+}
+
+/// Reads a setting, without its trailing newline.
+pub fn read(proto: &str, name: &str, key: &str) -> Result<String> {
+    let value = fs::read_to_string(conf_path(proto, name, key))?;
+    Ok(value.trim_end().to_string())
+}
+
+/// Reads a setting that holds a single integer.
+pub fn read_int(proto: &str, name: &str, key: &str) -> Result<i64> {
+    read(proto, name, key)?
+        .parse()
+        .map_err(|_| InterfacesError::Errno(Errno::EINVAL))
+}
+
+/// Writes a setting.
+pub fn write(proto: &str, name: &str, key: &str, value: &str) -> Result<()> {
+    fs::write(conf_path(proto, name, key), value)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conf_path() {
+        assert_eq!(
+            conf_path("ipv6", "eth0.100", "accept_ra"),
+            PathBuf::from("/proc/sys/net/ipv6/conf/eth0.100/accept_ra")
+        );
+        assert!(read_int("ipv4", "lo", "forwarding").is_ok());
+    }
+}
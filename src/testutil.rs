@@ -0,0 +1,90 @@
+//! Helpers shared by tests that need to create and destroy interfaces.  These run inside a
+//! private network namespace, so that they neither need nor disturb any real interfaces.
+
+use std::net;
+use std::thread;
+
+use nix::errno::Errno;
+use nix::sched::{self, CloneFlags};
+
+use libc;
+
+use link::{self, LinkKind};
+use netlink::{self, Request, NLM_F_CREATE, NLM_F_EXCL};
+use {Interface, InterfacesError, Result};
+
+// The attribute of address messages that holds the local address.
+const IFA_LOCAL: u16 = 2;
+
+/// Runs `f` on a new thread that has been moved into a fresh network namespace.  If we are not
+/// allowed to create namespaces (e.g. we are not root), the test is skipped.
+pub fn in_netns<F>(f: F)
+where
+    F: FnOnce() + Send,
+{
+    thread::scope(|s| {
+        let handle = s.spawn(|| {
+            match sched::unshare(CloneFlags::CLONE_NEWNET) {
+                Ok(()) => {}
+                Err(Errno::EPERM) => {
+                    println!("skipping test: cannot create a network namespace");
+                    return;
+                }
+                Err(e) => panic!("unshare failed: {}", e),
+            }
+
+            f();
+        });
+
+        if let Err(e) = handle.join() {
+            ::std::panic::resume_unwind(e);
+        }
+    });
+}
+
+/// Returns `Some(val)` on success, and `None` if the kernel does not support the requested
+/// operation (e.g. the relevant module is not available).  Panics on any other error.
+pub fn supported<T>(res: Result<T>) -> Option<T> {
+    match res {
+        Ok(val) => Some(val),
+        Err(InterfacesError::Errno(Errno::EOPNOTSUPP)) => {
+            println!("skipping test: not supported by this kernel");
+            None
+        }
+        Err(e) => panic!("unexpected error: {:?}", e),
+    }
+}
+
+/// Creates an Ethernet-like interface to stack other links on.  This is a dummy interface if the
+/// kernel supports them, and a bridge otherwise.
+pub fn create_parent(name: &str) -> Interface {
+    let dummy = LinkKind::Other("dummy".to_string());
+    match link::create_link(name, None, &dummy) {
+        Ok(iface) => iface,
+        Err(InterfacesError::Errno(Errno::EOPNOTSUPP)) => {
+            let bridge = LinkKind::Other("bridge".to_string());
+            link::create_link(name, None, &bridge).unwrap()
+        }
+        Err(e) => panic!("could not create {}: {:?}", name, e),
+    }
+}
+
+/// Adds an address to the interface, like `ip addr add <ip>/<prefix_len> dev <name>`.
+pub fn add_address(iface: &Interface, ip: &str, prefix_len: u8) {
+    let ip: net::IpAddr = ip.parse().unwrap();
+    let family = match ip {
+        net::IpAddr::V4(..) => libc::AF_INET,
+        net::IpAddr::V6(..) => libc::AF_INET6,
+    };
+
+    let mut req = Request::new(netlink::RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL);
+    req.put_u8(family as u8);
+    req.put_u8(prefix_len);
+    req.put_u8(0);
+    req.put_u8(0);
+    req.put_u32(iface.index().unwrap());
+    req.attr_ip(IFA_LOCAL, ip);
+
+    let mut sock = iface.netlink_socket(netlink::NETLINK_ROUTE).unwrap();
+    sock.request(req).unwrap();
+}
@@ -0,0 +1,289 @@
+//! TUN and TAP devices, which hand packets (or Ethernet frames) that are routed to an interface
+//! to a userspace process instead of a driver.  This is only available on Linux.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, IntoRawFd, RawFd};
+
+use libc::{self, c_short, ioctl};
+use nix::errno::Errno;
+
+use {constants, copy_slice, ffi, Interface, InterfacesError, Result};
+
+/// The path of the TUN/TAP clone device.
+const TUN_PATH: &str = "/dev/net/tun";
+
+// Flags passed to `TUNSETIFF`.
+const IFF_TUN: c_short = 0x0001;
+const IFF_TAP: c_short = 0x0002;
+const IFF_MULTI_QUEUE: c_short = 0x0100;
+const IFF_NO_PI: c_short = 0x1000;
+
+/// The type of a `Tun` device.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TunMode {
+    /// A TUN device, which carries IP packets.
+    Tun,
+
+    /// A TAP device, which carries Ethernet frames.
+    Tap,
+}
+
+/// The settings used to create a `Tun` device.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct TunOptions {
+    /// Whether this is a TUN or a TAP device.
+    pub mode: TunMode,
+
+    /// Whether each packet is prefixed with the 4-byte packet information header.  If this is
+    /// `false`, the device is created with `IFF_NO_PI`.
+    pub packet_info: bool,
+
+    /// Whether the device may have multiple queues.  Each additional queue is attached by
+    /// creating another `Tun` with the same name and options.
+    pub multi_queue: bool,
+
+    /// Whether the device outlives this process.  Non-persistent devices are removed as soon as
+    /// their last queue is closed.
+    pub persist: bool,
+
+    /// The user that is allowed to attach to the device without `CAP_NET_ADMIN`.
+    pub owner: Option<u32>,
+
+    /// The group that is allowed to attach to the device without `CAP_NET_ADMIN`.
+    pub group: Option<u32>,
+}
+
+impl TunOptions {
+    /// Returns the default options for a device of the given mode: a single queue without the
+    /// packet information header, which is removed when closed.
+    pub fn new(mode: TunMode) -> TunOptions {
+        TunOptions {
+            mode,
+            packet_info: false,
+            multi_queue: false,
+            persist: false,
+            owner: None,
+            group: None,
+        }
+    }
+
+    fn flags(&self) -> c_short {
+        let mut flags = match self.mode {
+            TunMode::Tun => IFF_TUN,
+            TunMode::Tap => IFF_TAP,
+        };
+        if !self.packet_info {
+            flags |= IFF_NO_PI;
+        }
+        if self.multi_queue {
+            flags |= IFF_MULTI_QUEUE;
+        }
+        flags
+    }
+}
+
+/// A queue of a TUN or TAP device.  Packets are exchanged with the kernel through the `Read` and
+/// `Write` implementations, each call transferring a single packet.
+#[derive(Debug)]
+pub struct Tun {
+    file: File,
+    name: String,
+    mode: TunMode,
+}
+
+impl Tun {
+    /// Creates a new TUN or TAP device, or attaches to an existing one with the same name.  If
+    /// `name` is `None`, the kernel picks one (e.g. `tun0`).  The name may also contain a `%d`,
+    /// which the kernel replaces with a number.
+    #[allow(non_snake_case)]
+    pub fn create(name: Option<&str>, options: &TunOptions) -> Result<Tun> {
+        let TUNSETIFF = match constants::get_constant("TUNSETIFF") {
+            Some(c) => c,
+            None => return Err(InterfacesError::NotSupported("TUNSETIFF")),
+        };
+
+        let file = OpenOptions::new().read(true).write(true).open(TUN_PATH)?;
+
+        let mut req = ffi::ifreq_with_tun_flags {
+            ifr_name: [0; ffi::IFNAMSIZ],
+            ifr_flags: options.flags(),
+            ifr_pad: [0; 22],
+        };
+
+        if let Some(name) = name {
+            if name.len() >= ffi::IFNAMSIZ {
+                return Err(InterfacesError::Errno(Errno::EINVAL));
+            }
+            copy_slice(&mut req.ifr_name, name.as_bytes());
+        }
+
+        let res = unsafe { ioctl(file.as_raw_fd(), TUNSETIFF, &mut req) };
+        if res < 0 {
+            return Err(InterfacesError::last_os_error());
+        }
+
+        // The kernel writes back the name it chose.
+        let len = req
+            .ifr_name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(ffi::IFNAMSIZ);
+        let name = String::from_utf8_lossy(&req.ifr_name[..len]).into_owned();
+
+        let tun = Tun {
+            file,
+            name,
+            mode: options.mode,
+        };
+
+        if let Some(owner) = options.owner {
+            tun.tun_ioctl("TUNSETOWNER", owner as libc::c_ulong)?;
+        }
+        if let Some(group) = options.group {
+            tun.tun_ioctl("TUNSETGROUP", group as libc::c_ulong)?;
+        }
+        if options.persist {
+            tun.set_persist(true)?;
+        }
+
+        Ok(tun)
+    }
+
+    /// Returns the name of this device.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether this is a TUN or a TAP device.
+    pub fn mode(&self) -> TunMode {
+        self.mode
+    }
+
+    /// Returns the `Interface` for this device, which can be used to configure it.
+    pub fn interface(&self) -> Result<Interface> {
+        match Interface::get_by_name(&self.name)? {
+            Some(iface) => Ok(iface),
+            None => Err(InterfacesError::Errno(Errno::ENODEV)),
+        }
+    }
+
+    /// Sets whether this device outlives the process that created it.
+    pub fn set_persist(&self, persist: bool) -> Result<()> {
+        self.tun_ioctl("TUNSETPERSIST", persist as libc::c_ulong)
+    }
+
+    #[allow(non_snake_case)]
+    fn tun_ioctl(&self, name: &'static str, arg: libc::c_ulong) -> Result<()> {
+        let REQUEST = match constants::get_constant(name) {
+            Some(c) => c,
+            None => return Err(InterfacesError::NotSupported(name)),
+        };
+
+        let res = unsafe { ioctl(self.file.as_raw_fd(), REQUEST, arg) };
+        if res < 0 {
+            return Err(InterfacesError::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+impl Read for Tun {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Read for &Tun {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.file).read(buf)
+    }
+}
+
+impl Write for Tun {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Write for &Tun {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.file).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRawFd for Tun {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for Tun {
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use address::LinkType;
+    use link::LinkKind;
+    use testutil;
+
+    #[test]
+    fn test_create_tap() {
+        testutil::in_netns(|| {
+            let tap = match Tun::create(Some("tap%d"), &TunOptions::new(TunMode::Tap)) {
+                Ok(tap) => tap,
+                Err(InterfacesError::Errno(Errno::ENOENT)) => return,
+                Err(e) => panic!("could not create tap: {:?}", e),
+            };
+            assert_eq!(tap.name(), "tap0");
+
+            let iface = tap.interface().unwrap();
+            let kind = iface.link_info().unwrap().kind;
+            assert_eq!(kind, Some(LinkKind::Other("tun".to_string())));
+
+            // Non-persistent devices go away when closed.
+            drop(tap);
+            assert!(Interface::get_by_name("tap0").unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_tun_write() {
+        testutil::in_netns(|| {
+            let tun = match Tun::create(None, &TunOptions::new(TunMode::Tun)) {
+                Ok(tun) => tun,
+                Err(InterfacesError::Errno(Errno::ENOENT)) => return,
+                Err(e) => panic!("could not create tun: {:?}", e),
+            };
+
+            let mut iface = tun.interface().unwrap();
+            iface.set_up(true).unwrap();
+
+            // TUN devices have no link-layer address at all.
+            let link = iface.link_address().unwrap();
+            assert_eq!(link.hardware_type, LinkType::None);
+            assert!(link.address().is_empty());
+            assert!(iface.hardware_addr().is_err());
+
+            // A minimal IPv4 header, which the kernel will happily drop.
+            let mut packet = [0u8; 20];
+            packet[0] = 0x45;
+            packet[3] = 20;
+            packet[8] = 64;
+            assert_eq!((&tun).write(&packet).unwrap(), packet.len());
+        });
+    }
+}
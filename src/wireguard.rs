@@ -0,0 +1,788 @@
+//! Configuring WireGuard devices through the `wireguard` generic netlink family.  This is only
+//! available on Linux.
+
+use std::error::Error;
+use std::fmt;
+use std::net;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use libc;
+
+use link::{self, LinkKind};
+use netlink::{self, Attrs, Request};
+use {Interface, Result};
+
+const WG_GENL_NAME: &str = "wireguard";
+const WG_GENL_VERSION: u8 = 1;
+
+const WG_CMD_GET_DEVICE: u8 = 0;
+const WG_CMD_SET_DEVICE: u8 = 1;
+
+const WGDEVICE_A_IFINDEX: u16 = 1;
+const WGDEVICE_A_PRIVATE_KEY: u16 = 3;
+const WGDEVICE_A_PUBLIC_KEY: u16 = 4;
+const WGDEVICE_A_FLAGS: u16 = 5;
+const WGDEVICE_A_LISTEN_PORT: u16 = 6;
+const WGDEVICE_A_FWMARK: u16 = 7;
+const WGDEVICE_A_PEERS: u16 = 8;
+
+const WGDEVICE_F_REPLACE_PEERS: u32 = 1;
+
+const WGPEER_A_PUBLIC_KEY: u16 = 1;
+const WGPEER_A_PRESHARED_KEY: u16 = 2;
+const WGPEER_A_FLAGS: u16 = 3;
+const WGPEER_A_ENDPOINT: u16 = 4;
+const WGPEER_A_PERSISTENT_KEEPALIVE_INTERVAL: u16 = 5;
+const WGPEER_A_LAST_HANDSHAKE_TIME: u16 = 6;
+const WGPEER_A_RX_BYTES: u16 = 7;
+const WGPEER_A_TX_BYTES: u16 = 8;
+const WGPEER_A_ALLOWEDIPS: u16 = 9;
+
+const WGPEER_F_REMOVE_ME: u32 = 1;
+const WGPEER_F_REPLACE_ALLOWEDIPS: u32 = 2;
+
+const WGALLOWEDIP_A_FAMILY: u16 = 1;
+const WGALLOWEDIP_A_IPADDR: u16 = 2;
+const WGALLOWEDIP_A_CIDR_MASK: u16 = 3;
+
+/// The length of WireGuard keys, in bytes.
+pub const KEY_LEN: usize = 32;
+
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The error returned when a `Key` cannot be parsed from base64.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyError {
+    /// The string has the given length instead of 44 characters.
+    InvalidLength(usize),
+
+    /// The string contains a character that is not part of the base64 alphabet.
+    InvalidCharacter(char),
+
+    /// The string does not end in a single `=`, or its last character has bits set that do not
+    /// belong to the key.
+    InvalidPadding,
+}
+
+impl Error for KeyError {}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyError::InvalidLength(len) => {
+                write!(f, "invalid key length {}, expected 44 characters", len)
+            }
+            KeyError::InvalidCharacter(c) => write!(f, "invalid character {:?} in key", c),
+            KeyError::InvalidPadding => write!(f, "invalid key padding"),
+        }
+    }
+}
+
+/// A WireGuard key (private, public or preshared).  Keys are formatted and parsed in base64,
+/// like the `wg` tool does.  Since a `Key` may be secret, its `Debug` output leaves out the key
+/// itself; use `Display` or `to_base64` to print it.
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Key([u8; KEY_LEN]);
+
+impl Key {
+    /// Creates a key from its raw bytes.
+    pub fn new(bytes: [u8; KEY_LEN]) -> Key {
+        Key(bytes)
+    }
+
+    /// Returns the raw bytes of this key.
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+
+    /// Formats this key in base64.
+    ///
+    /// ```
+    /// # use interfaces::wireguard::Key;
+    /// let key = Key::new([0; 32]);
+    /// assert_eq!(key.to_base64(), "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=");
+    /// ```
+    pub fn to_base64(&self) -> String {
+        let mut ret = String::with_capacity(44);
+        for chunk in self.0.chunks(3) {
+            let b = [
+                chunk[0],
+                chunk.get(1).cloned().unwrap_or(0),
+                chunk.get(2).cloned().unwrap_or(0),
+            ];
+            let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | (b[2] as u32);
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    ret.push(BASE64_ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+                } else {
+                    ret.push('=');
+                }
+            }
+        }
+        ret
+    }
+
+    /// Parses a key from base64.  Returns an error if the string is not a valid 32-byte key.
+    pub fn from_base64(s: &str) -> ::std::result::Result<Key, KeyError> {
+        let is_valid = |c: char| c == '=' || (c.is_ascii() && BASE64_ALPHABET.contains(&(c as u8)));
+        if let Some(c) = s.chars().find(|&c| !is_valid(c)) {
+            return Err(KeyError::InvalidCharacter(c));
+        }
+        if s.len() != 44 {
+            return Err(KeyError::InvalidLength(s.len()));
+        }
+        let s = s.as_bytes();
+        if s[43] != b'=' || s[..43].contains(&b'=') {
+            return Err(KeyError::InvalidPadding);
+        }
+
+        let mut bytes = [0u8; KEY_LEN];
+        let mut acc = 0u32;
+        let mut bits = 0;
+        let mut pos = 0;
+        for &c in &s[..43] {
+            // Only characters of the alphabet are left, so this cannot fail.
+            let v = BASE64_ALPHABET.iter().position(|&a| a == c).unwrap() as u32;
+            acc = (acc << 6) | v;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                if pos < KEY_LEN {
+                    bytes[pos] = (acc >> bits) as u8;
+                }
+                pos += 1;
+            }
+        }
+
+        // The last character only carries 4 meaningful bits; the rest must be zero.
+        if acc & ((1 << bits) - 1) != 0 {
+            return Err(KeyError::InvalidPadding);
+        }
+
+        Ok(Key(bytes))
+    }
+
+    fn parse(data: &[u8]) -> Option<Key> {
+        if data.len() != KEY_LEN {
+            return None;
+        }
+        let mut bytes = [0u8; KEY_LEN];
+        bytes.copy_from_slice(data);
+        Some(Key(bytes))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_base64())
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Key(..)")
+    }
+}
+
+impl FromStr for Key {
+    type Err = KeyError;
+
+    fn from_str(s: &str) -> ::std::result::Result<Key, KeyError> {
+        Key::from_base64(s)
+    }
+}
+
+/// An address range whose traffic is routed to (and accepted from) a peer.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct AllowedIp {
+    /// The network address.
+    pub addr: net::IpAddr,
+
+    /// The prefix length of the network.
+    pub prefix_len: u8,
+}
+
+impl AllowedIp {
+    fn put(&self, req: &mut Request) {
+        let entry = req.begin_nested(0);
+        let family = match self.addr {
+            net::IpAddr::V4(..) => libc::AF_INET,
+            net::IpAddr::V6(..) => libc::AF_INET6,
+        };
+        req.attr_u16(WGALLOWEDIP_A_FAMILY, family as u16);
+        req.attr_ip(WGALLOWEDIP_A_IPADDR, self.addr);
+        req.attr_u8(WGALLOWEDIP_A_CIDR_MASK, self.prefix_len);
+        req.end_nested(entry);
+    }
+
+    fn parse(data: &[u8]) -> Option<AllowedIp> {
+        let mut addr = None;
+        let mut prefix_len = None;
+        for (ty, val) in Attrs::new(data) {
+            match ty {
+                WGALLOWEDIP_A_IPADDR => addr = netlink::parse_ip(val),
+                WGALLOWEDIP_A_CIDR_MASK => prefix_len = netlink::parse_u8(val),
+                _ => {}
+            }
+        }
+
+        Some(AllowedIp {
+            addr: addr?,
+            prefix_len: prefix_len?,
+        })
+    }
+}
+
+impl fmt::Display for AllowedIp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// The changes to make to a single peer of a device.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PeerConfig {
+    /// The public key identifying the peer.
+    pub public_key: Key,
+
+    /// The preshared key to mix into the handshake, if any.
+    pub preshared_key: Option<Key>,
+
+    /// The address to send packets for this peer to.
+    pub endpoint: Option<net::SocketAddr>,
+
+    /// The address ranges to add to the peer.
+    pub allowed_ips: Vec<AllowedIp>,
+
+    /// Whether `allowed_ips` replaces the existing address ranges, rather than adding to them.
+    pub replace_allowed_ips: bool,
+
+    /// How often to send keepalive packets, in seconds.  `Some(0)` disables them, and `None`
+    /// leaves the current setting alone.
+    pub persistent_keepalive: Option<u16>,
+
+    /// Whether to remove the peer from the device instead of updating it.
+    pub remove: bool,
+}
+
+impl PeerConfig {
+    /// Returns a configuration that adds (or updates) the peer with the given public key, but
+    /// does not change any of its settings.
+    pub fn new(public_key: Key) -> PeerConfig {
+        PeerConfig {
+            public_key,
+            preshared_key: None,
+            endpoint: None,
+            allowed_ips: vec![],
+            replace_allowed_ips: false,
+            persistent_keepalive: None,
+            remove: false,
+        }
+    }
+
+    fn put(&self, req: &mut Request) {
+        let entry = req.begin_nested(0);
+        req.attr(WGPEER_A_PUBLIC_KEY, self.public_key.as_bytes());
+
+        let mut flags = 0;
+        if self.remove {
+            flags |= WGPEER_F_REMOVE_ME;
+        }
+        if self.replace_allowed_ips {
+            flags |= WGPEER_F_REPLACE_ALLOWEDIPS;
+        }
+        req.attr_u32(WGPEER_A_FLAGS, flags);
+
+        if let Some(ref key) = self.preshared_key {
+            req.attr(WGPEER_A_PRESHARED_KEY, key.as_bytes());
+        }
+        if let Some(endpoint) = self.endpoint {
+            req.attr(WGPEER_A_ENDPOINT, &sockaddr_bytes(endpoint));
+        }
+        if let Some(interval) = self.persistent_keepalive {
+            req.attr_u16(WGPEER_A_PERSISTENT_KEEPALIVE_INTERVAL, interval);
+        }
+
+        let ips = req.begin_nested(WGPEER_A_ALLOWEDIPS);
+        for ip in &self.allowed_ips {
+            ip.put(req);
+        }
+        req.end_nested(ips);
+
+        req.end_nested(entry);
+    }
+}
+
+/// The changes to make to a WireGuard device.  Settings that are `None` are left alone.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct DeviceConfig {
+    /// The private key of the device.
+    pub private_key: Option<Key>,
+
+    /// The UDP port to listen on.  `Some(0)` picks a random port.
+    pub listen_port: Option<u16>,
+
+    /// The firewall mark applied to outgoing packets.  `Some(0)` clears it.
+    pub fwmark: Option<u32>,
+
+    /// Whether `peers` replaces all existing peers, rather than being merged into them.
+    pub replace_peers: bool,
+
+    /// The peers to add, update or remove.
+    pub peers: Vec<PeerConfig>,
+}
+
+impl DeviceConfig {
+    fn put(&self, req: &mut Request) {
+        if let Some(ref key) = self.private_key {
+            req.attr(WGDEVICE_A_PRIVATE_KEY, key.as_bytes());
+        }
+        if let Some(port) = self.listen_port {
+            req.attr_u16(WGDEVICE_A_LISTEN_PORT, port);
+        }
+        if let Some(fwmark) = self.fwmark {
+            req.attr_u32(WGDEVICE_A_FWMARK, fwmark);
+        }
+        if self.replace_peers {
+            req.attr_u32(WGDEVICE_A_FLAGS, WGDEVICE_F_REPLACE_PEERS);
+        }
+
+        let peers = req.begin_nested(WGDEVICE_A_PEERS);
+        for peer in &self.peers {
+            peer.put(req);
+        }
+        req.end_nested(peers);
+    }
+}
+
+/// The current state of a peer of a WireGuard device.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Peer {
+    /// The public key identifying the peer.
+    pub public_key: Key,
+
+    /// The preshared key mixed into the handshake, if any.
+    pub preshared_key: Option<Key>,
+
+    /// The current address of the peer.
+    pub endpoint: Option<net::SocketAddr>,
+
+    /// The address ranges routed to the peer.
+    pub allowed_ips: Vec<AllowedIp>,
+
+    /// How often keepalive packets are sent, in seconds, if they are enabled.
+    pub persistent_keepalive: Option<u16>,
+
+    /// The time of the last successful handshake, if there has been one.
+    pub last_handshake: Option<SystemTime>,
+
+    /// The number of bytes received from the peer.
+    pub rx_bytes: u64,
+
+    /// The number of bytes sent to the peer.
+    pub tx_bytes: u64,
+}
+
+impl Peer {
+    fn parse(data: &[u8]) -> Option<Peer> {
+        let mut public_key = None;
+        let mut ret = Peer {
+            public_key: Key([0; KEY_LEN]),
+            preshared_key: None,
+            endpoint: None,
+            allowed_ips: vec![],
+            persistent_keepalive: None,
+            last_handshake: None,
+            rx_bytes: 0,
+            tx_bytes: 0,
+        };
+
+        for (ty, val) in Attrs::new(data) {
+            match ty {
+                WGPEER_A_PUBLIC_KEY => public_key = Key::parse(val),
+                WGPEER_A_PRESHARED_KEY => {
+                    // An unset preshared key is reported as all zeroes.
+                    ret.preshared_key = Key::parse(val).filter(|k| k.0 != [0; KEY_LEN])
+                }
+                WGPEER_A_ENDPOINT => ret.endpoint = parse_sockaddr(val),
+                WGPEER_A_PERSISTENT_KEEPALIVE_INTERVAL => {
+                    ret.persistent_keepalive = netlink::parse_u16(val).filter(|&i| i != 0)
+                }
+                WGPEER_A_LAST_HANDSHAKE_TIME => ret.last_handshake = parse_timespec(val),
+                WGPEER_A_RX_BYTES => ret.rx_bytes = netlink::parse_u64(val).unwrap_or(0),
+                WGPEER_A_TX_BYTES => ret.tx_bytes = netlink::parse_u64(val).unwrap_or(0),
+                WGPEER_A_ALLOWEDIPS => {
+                    ret.allowed_ips = Attrs::new(val)
+                        .filter_map(|(_, ip)| AllowedIp::parse(ip))
+                        .collect()
+                }
+                _ => {}
+            }
+        }
+
+        ret.public_key = public_key?;
+        Some(ret)
+    }
+}
+
+/// The current state of a WireGuard device.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Device {
+    /// The private key of the device, if one is set.
+    pub private_key: Option<Key>,
+
+    /// The public key of the device, if a private key is set.
+    pub public_key: Option<Key>,
+
+    /// The UDP port the device listens on.
+    pub listen_port: u16,
+
+    /// The firewall mark applied to outgoing packets, or 0 if there is none.
+    pub fwmark: u32,
+
+    /// The peers of the device.
+    pub peers: Vec<Peer>,
+}
+
+impl Device {
+    /// Adds the attributes of one message of a `WG_CMD_GET_DEVICE` dump.  Large devices are
+    /// split over several messages, and a peer with many allowed IPs may continue from one
+    /// message into the next.
+    fn merge(&mut self, data: Attrs) {
+        for (ty, val) in data {
+            match ty {
+                WGDEVICE_A_PRIVATE_KEY => {
+                    self.private_key = Key::parse(val).filter(|k| k.0 != [0; KEY_LEN])
+                }
+                WGDEVICE_A_PUBLIC_KEY => {
+                    self.public_key = Key::parse(val).filter(|k| k.0 != [0; KEY_LEN])
+                }
+                WGDEVICE_A_LISTEN_PORT => self.listen_port = netlink::parse_u16(val).unwrap_or(0),
+                WGDEVICE_A_FWMARK => self.fwmark = netlink::parse_u32(val).unwrap_or(0),
+                WGDEVICE_A_PEERS => {
+                    for peer in Attrs::new(val).filter_map(|(_, p)| Peer::parse(p)) {
+                        match self.peers.last_mut() {
+                            Some(ref mut last) if last.public_key == peer.public_key => {
+                                last.allowed_ips.extend(peer.allowed_ips)
+                            }
+                            _ => self.peers.push(peer),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Interface {
+    /// Creates a new WireGuard device with the given name, and returns it.  The device has no
+    /// keys or peers until it is configured with `configure_wireguard`.
+    pub fn create_wireguard(name: &str) -> Result<Interface> {
+        link::create_link(name, None, &LinkKind::Other("wireguard".to_string()))
+    }
+
+    /// Retrieves the configuration and peer statistics of this WireGuard device.
+    pub fn wireguard_device(&self) -> Result<Device> {
+        let mut sock = self.netlink_socket(netlink::NETLINK_GENERIC)?;
+        let family = sock.resolve_family(WG_GENL_NAME)?;
+
+        let mut req = Request::generic(family, WG_CMD_GET_DEVICE, WG_GENL_VERSION, 0);
+        req.attr_u32(WGDEVICE_A_IFINDEX, self.index()?);
+
+        let mut ret = Device {
+            private_key: None,
+            public_key: None,
+            listen_port: 0,
+            fwmark: 0,
+            peers: vec![],
+        };
+        for msg in sock.dump(req)? {
+            ret.merge(msg.attrs(netlink::GENLMSGHDR_LEN));
+        }
+
+        Ok(ret)
+    }
+
+    /// Applies the given changes to this WireGuard device.
+    pub fn configure_wireguard(&self, config: &DeviceConfig) -> Result<()> {
+        let mut sock = self.netlink_socket(netlink::NETLINK_GENERIC)?;
+        let family = sock.resolve_family(WG_GENL_NAME)?;
+
+        let mut req = Request::generic(family, WG_CMD_SET_DEVICE, WG_GENL_VERSION, 0);
+        req.attr_u32(WGDEVICE_A_IFINDEX, self.index()?);
+        config.put(&mut req);
+
+        sock.request(req)
+    }
+}
+
+/// Encodes a socket address as a `sockaddr_in` or `sockaddr_in6`.
+fn sockaddr_bytes(addr: net::SocketAddr) -> Vec<u8> {
+    let mut ret = vec![];
+    match addr {
+        net::SocketAddr::V4(addr) => {
+            ret.extend_from_slice(&(libc::AF_INET as u16).to_ne_bytes());
+            ret.extend_from_slice(&addr.port().to_be_bytes());
+            ret.extend_from_slice(&addr.ip().octets());
+            ret.extend_from_slice(&[0; 8]);
+        }
+        net::SocketAddr::V6(addr) => {
+            ret.extend_from_slice(&(libc::AF_INET6 as u16).to_ne_bytes());
+            ret.extend_from_slice(&addr.port().to_be_bytes());
+            ret.extend_from_slice(&addr.flowinfo().to_be_bytes());
+            ret.extend_from_slice(&addr.ip().octets());
+            ret.extend_from_slice(&addr.scope_id().to_ne_bytes());
+        }
+    }
+    ret
+}
+
+/// Decodes a `sockaddr_in` or `sockaddr_in6`.
+fn parse_sockaddr(data: &[u8]) -> Option<net::SocketAddr> {
+    let family = netlink::parse_u16(data)? as i32;
+    if data.len() < 4 {
+        return None;
+    }
+    let port = u16::from_be_bytes([data[2], data[3]]);
+
+    match family {
+        libc::AF_INET if data.len() >= 8 => {
+            let ip = net::Ipv4Addr::new(data[4], data[5], data[6], data[7]);
+            Some(net::SocketAddr::V4(net::SocketAddrV4::new(ip, port)))
+        }
+        libc::AF_INET6 if data.len() >= 28 => {
+            let flowinfo = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&data[8..24]);
+            let scope_id = netlink::parse_u32(&data[24..28])?;
+            Some(net::SocketAddr::V6(net::SocketAddrV6::new(
+                net::Ipv6Addr::from(octets),
+                port,
+                flowinfo,
+                scope_id,
+            )))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes a `struct __kernel_timespec`.  A zero time means that there was no handshake.
+fn parse_timespec(data: &[u8]) -> Option<SystemTime> {
+    if data.len() < 16 {
+        return None;
+    }
+    let secs = netlink::parse_u64(&data[0..8])?;
+    let nanos = netlink::parse_u64(&data[8..16])?;
+    if secs == 0 && nanos == 0 {
+        return None;
+    }
+    Some(UNIX_EPOCH + Duration::new(secs, nanos as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netns::Netns;
+    use std::thread;
+    use testutil;
+
+    #[test]
+    fn test_key_base64() {
+        let s = "yAnz5TF+lXXJte14tji3zlMNq+hd2rYUIgJBgB3fBmk=";
+        let key = Key::from_base64(s).unwrap();
+        assert_eq!(key.as_bytes()[0], 0xc8);
+        assert_eq!(key.to_base64(), s);
+        assert_eq!(s.parse::<Key>(), Ok(key));
+
+        assert_eq!(
+            Key::from_base64("yAnz5TF+lXXJte14tji3zlMNq+hd2rYUIgJBgB3fBm="),
+            Err(KeyError::InvalidLength(43))
+        );
+        assert_eq!(
+            Key::from_base64("yAnz5TF+lXXJte14tji3zlMNq+hd2rYUIgJBgB3fBml="),
+            Err(KeyError::InvalidPadding)
+        );
+        assert_eq!(
+            "yAnz5TF+lXXJte14tji3zlMNq+hd2rYUIgJBgB3fBmkk".parse::<Key>(),
+            Err(KeyError::InvalidPadding)
+        );
+        assert_eq!(
+            "yAnz5TF+lXXJte14tji3zlMNq-hd2rYUIgJBgB3fBmk=".parse::<Key>(),
+            Err(KeyError::InvalidCharacter('-'))
+        );
+    }
+
+    #[test]
+    fn test_key_debug_is_redacted() {
+        let key = Key::new([7; KEY_LEN]);
+        let config = DeviceConfig {
+            private_key: Some(key),
+            peers: vec![PeerConfig {
+                preshared_key: Some(key),
+                ..PeerConfig::new(Key::new([1; KEY_LEN]))
+            }],
+            ..DeviceConfig::default()
+        };
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains(&key.to_base64()));
+        assert!(!debug.contains(&Key::new([1; KEY_LEN]).to_base64()));
+        assert_eq!(format!("{:?}", key), "Key(..)");
+    }
+
+    #[test]
+    fn test_device_config_parse() {
+        let peer = PeerConfig {
+            endpoint: Some("[fd00::1]:51820".parse().unwrap()),
+            allowed_ips: vec![
+                AllowedIp {
+                    addr: "10.0.0.0".parse().unwrap(),
+                    prefix_len: 24,
+                },
+                AllowedIp {
+                    addr: "fd00::".parse().unwrap(),
+                    prefix_len: 64,
+                },
+            ],
+            persistent_keepalive: Some(25),
+            ..PeerConfig::new(Key::new([1; KEY_LEN]))
+        };
+        let config = DeviceConfig {
+            listen_port: Some(51820),
+            fwmark: Some(7),
+            peers: vec![peer.clone()],
+            ..DeviceConfig::default()
+        };
+
+        // The kernel reports devices using the same attributes that configure them.
+        let mut req = Request::generic(0, WG_CMD_SET_DEVICE, WG_GENL_VERSION, 0);
+        config.put(&mut req);
+
+        let mut device = Device {
+            private_key: None,
+            public_key: None,
+            listen_port: 0,
+            fwmark: 0,
+            peers: vec![],
+        };
+        device.merge(Attrs::new(&req.body()[netlink::GENLMSGHDR_LEN..]));
+
+        assert_eq!(device.listen_port, 51820);
+        assert_eq!(device.fwmark, 7);
+        assert_eq!(device.peers.len(), 1);
+        assert_eq!(device.peers[0].public_key, peer.public_key);
+        assert_eq!(device.peers[0].endpoint, peer.endpoint);
+        assert_eq!(device.peers[0].allowed_ips, peer.allowed_ips);
+        assert_eq!(device.peers[0].persistent_keepalive, Some(25));
+        assert_eq!(device.peers[0].last_handshake, None);
+    }
+
+    #[test]
+    fn test_configure_wireguard() {
+        testutil::in_netns(|| {
+            let wg = match testutil::supported(Interface::create_wireguard("wg0")) {
+                Some(wg) => wg,
+                None => return,
+            };
+
+            let config = DeviceConfig {
+                private_key: Some(Key::new([2; KEY_LEN])),
+                listen_port: Some(51820),
+                peers: vec![PeerConfig {
+                    allowed_ips: vec![AllowedIp {
+                        addr: "10.0.0.2".parse().unwrap(),
+                        prefix_len: 32,
+                    }],
+                    ..PeerConfig::new(Key::new([3; KEY_LEN]))
+                }],
+                ..DeviceConfig::default()
+            };
+            wg.configure_wireguard(&config).unwrap();
+
+            let device = wg.wireguard_device().unwrap();
+            assert_eq!(device.private_key, config.private_key);
+            assert!(device.public_key.is_some());
+            assert_eq!(device.listen_port, 51820);
+            assert_eq!(device.peers.len(), 1);
+            assert_eq!(device.peers[0].allowed_ips, config.peers[0].allowed_ips);
+            assert_eq!(device.peers[0].rx_bytes, 0);
+        });
+    }
+
+    #[test]
+    fn test_handshake_between_netns() {
+        testutil::in_netns(|| {
+            let (mut veth0, veth1) = Interface::create_veth("veth0", "veth1").unwrap();
+            let netns = Netns::new().unwrap();
+            let mut veth1 = veth1.move_to_netns(&netns).unwrap();
+            testutil::add_address(&veth0, "10.9.0.1", 24);
+            testutil::add_address(&veth1, "10.9.0.2", 24);
+            veth0.set_up(true).unwrap();
+            veth1.set_up(true).unwrap();
+
+            let mut wg0 = match testutil::supported(Interface::create_wireguard("wg0")) {
+                Some(wg) => wg,
+                None => return,
+            };
+            let mut wg1 = netns
+                .run(|| Interface::create_wireguard("wg1"))
+                .unwrap()
+                .unwrap();
+
+            // Give both devices a key first, so that each can be told the other's public key.
+            for (wg, key, port) in [(&wg0, 1, 51820), (&wg1, 2, 51821)] {
+                let config = DeviceConfig {
+                    private_key: Some(Key::new([key; KEY_LEN])),
+                    listen_port: Some(port),
+                    ..DeviceConfig::default()
+                };
+                wg.configure_wireguard(&config).unwrap();
+            }
+            let key0 = wg0.wireguard_device().unwrap().public_key.unwrap();
+            let key1 = wg1.wireguard_device().unwrap().public_key.unwrap();
+
+            let peers = [
+                (&wg0, key1, "10.9.0.2:51821", "10.10.0.2", "10.10.0.1"),
+                (&wg1, key0, "10.9.0.1:51820", "10.10.0.1", "10.10.0.2"),
+            ];
+            for (wg, key, endpoint, allowed, local) in peers {
+                let config = DeviceConfig {
+                    peers: vec![PeerConfig {
+                        endpoint: Some(endpoint.parse().unwrap()),
+                        allowed_ips: vec![AllowedIp {
+                            addr: allowed.parse().unwrap(),
+                            prefix_len: 32,
+                        }],
+                        ..PeerConfig::new(key)
+                    }],
+                    ..DeviceConfig::default()
+                };
+                wg.configure_wireguard(&config).unwrap();
+                testutil::add_address(wg, local, 24);
+            }
+            wg0.set_up(true).unwrap();
+            wg1.set_up(true).unwrap();
+
+            let device = wg0.wireguard_device().unwrap();
+            assert_eq!(device.listen_port, 51820);
+            assert_eq!(device.peers.len(), 1);
+            assert_eq!(device.peers[0].public_key, key1);
+            assert_eq!(device.peers[0].last_handshake, None);
+
+            // Sending a packet into the tunnel starts the handshake.
+            let sock = net::UdpSocket::bind("10.10.0.1:0").unwrap();
+            for _ in 0..50 {
+                sock.send_to(b"hello", "10.10.0.2:9").unwrap();
+                let peer = &wg0.wireguard_device().unwrap().peers[0];
+                if peer.last_handshake.is_some() {
+                    assert!(peer.tx_bytes > 0);
+                    assert!(peer.rx_bytes > 0);
+                    return;
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            panic!("no handshake with the peer");
+        });
+    }
+}